@@ -1,41 +1,74 @@
 use crate::prelude::*;
 
-impl Object {
-    //Attempts to move an object, modifying its position
-    pub fn try_move(&mut self, dest: Point, map: &Map) {
-        if self.pos.is_some() {
-            if !map.walkable(dest.x, dest.y) {
-                return
-            }
-            else {
-                self.pos = Some(dest);
-                if let Object { viewshed: Some(view), .. } = self {
-                    view.refresh = true
-                }
-                if let Object { health: Some(health), .. } = self { health.set_regen_valid(false); }
-            }
+//Attempts to move an entity, modifying its position. Returns false if it couldn't move, including when it has no position to move from.
+pub fn try_move(ecs: &mut Ecs, id: EntityId, dest: Point, map: &Map) -> bool {
+    if let Some(pos) = ecs.positions.get_mut(id) {
+        if !map.walkable(dest.x, dest.y) {
+            return false
         }
         else {
-            console::log("ERROR: Entity attempted to move without positional component.")
+            *pos = dest;
+            //Wading into shallow water costs a turn
+            if map.tiles[map.index(dest.x, dest.y)] == TileClass::ShallowWater { ecs.delays.insert(id, 1) }
+            if let Some(view) = ecs.viewsheds.get_mut(id) { view.refresh = true }
+            if let Some(health) = ecs.healths.get_mut(id) { health.set_regen_valid(false); }
+            spring_traps_at(ecs, id, dest);
+            return true
         }
     }
+    return false
+}
 
-    //Attempts to attack another entity
-    pub fn try_attack(&mut self, target: &mut Object, rng: &mut RandomNumberGenerator) {
-        if let Object { damage: Some(dmg), .. } = self {
-            if let Object { health: Some(health), .. } = self { health.set_regen_valid(false); }
-            if let Object { health: Some(tgt_health), .. } = target {
-                //TODO: Add to-hit rolls?
-                let dmgval = dmg.roll(rng);
-                tgt_health.wounds.push(dmgval);
-            }
-            else {
-                console::log("ERROR: Attack was wrongfully attempted against a non-damageable entity.")
-            }
-        }
-        else {
-            console::log("ERROR: Entity attempted to attack without damage component.")
+//Sets off any trap on a tile something just stepped onto; what the trap does is worked out in proc_traps
+fn spring_traps_at(ecs: &mut Ecs, id: EntityId, dest: Point) {
+    let floor = ecs.get_floor(id);
+    let positions = &ecs.positions;
+    let floors = &ecs.floors;
+    for (trap_id, trap) in ecs.traps.iter_mut() {
+        let on_floor = floors.get(trap_id).map_or(false, |f| *f == floor);
+        if on_floor && positions.get(trap_id) == Some(&dest) {
+            trap.triggered_by = Some(id);
         }
     }
 }
 
+//Attempts to open a closed door, letting everything take a fresh look through it
+pub fn try_open_door(ecs: &mut Ecs, id: EntityId, dest: Point, map: &mut Map) -> bool {
+    if !map.open_door(dest) { return false }
+
+    for (_, view) in ecs.viewsheds.iter_mut() { view.refresh = true }
+    if let Some(health) = ecs.healths.get_mut(id) { health.set_regen_valid(false); }
+    return true
+}
+//Closes every open door next to an entity that has nothing standing in it
+pub fn try_close_doors(ecs: &mut Ecs, id: EntityId, map: &mut Map) -> bool {
+    let pos = match ecs.positions.get(id) {
+        Some(pos) => *pos,
+        None => return false
+    };
+
+    let mut closed = false;
+    for n in pos.get_neighbors().into_iter() {
+        if map.in_bounds(n.x, n.y) && map.close_door(n) { closed = true }
+    }
+    if closed {
+        for (_, view) in ecs.viewsheds.iter_mut() { view.refresh = true }
+    }
+    return closed
+}
+
+//Attempts to attack another entity. Returns false if no attack was made, either because the attacker can't deal damage
+//or because the target can't take it.
+pub fn try_attack(ecs: &mut Ecs, attacker: EntityId, target: EntityId, rng: &mut RandomNumberGenerator) -> bool {
+    let dmg = match ecs.damages.get(attacker) {
+        Some(dmg) => dmg,
+        None => return false
+    };
+    if !ecs.healths.contains(target) { return false }
+
+    if let Some(health) = ecs.healths.get_mut(attacker) { health.set_regen_valid(false); }
+    //TODO: Add to-hit rolls?
+    let dmgval = dmg.roll(rng);
+    ecs.healths.get_mut(target).unwrap().wounds.push(dmgval);
+    return true
+}
@@ -11,7 +11,7 @@ pub enum AIState {
 #[derive(Serialize, Deserialize, Clone)]
 pub struct AIClass {
     pub state: AIState,
-    pub target: Option<usize>,
+    pub target: Option<EntityId>,
     pub tgt_memory: u16,
     pub tgt_heatmap: HeatMap   
 }
@@ -54,9 +54,11 @@ impl HeatMap {
             new_graph.append(&mut p.get_neighbors());
         }
 
-        //Deduplicate, drop unwalkable tiles, drop tiles around the enemy, and drop tiles that are already in the old graph
+        //Deduplicate, drop unwalkable tiles, drop tiles around the enemy, and drop tiles that are already in the old graph.
+        //Heat spreads through closed doors, since whatever is being hunted could have gone through them.
         new_graph.dedup();
-        new_graph.retain(|p| map.walkable(p.x, p.y));
+        new_graph.retain(|p| map.in_bounds(p.x, p.y) && (map.walkable(p.x, p.y) || map.openable(p.x, p.y)));
+        new_graph.retain(|p| !map.tiles[map.index(p.x, p.y)].is_hazard());
         new_graph.retain(|p| !ai_area.contains(p));
         new_graph.retain(|p| !self.old_nodes.contains(p));
 
@@ -99,7 +101,7 @@ impl HeatMap {
             targets.sort();
             targets
         };
-        let dijkstra_map = DijkstraMap::new(80, 80, &targets, map, 64.0);
+        let dijkstra_map = DijkstraMap::new(map.width, map.height, &targets, map, 64.0);
         return if let Some(destidx) = DijkstraMap::find_lowest_exit(&dijkstra_map, map.index(start.x, start.y), map) {
             map.point_from_idx(destidx)
         } else {
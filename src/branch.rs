@@ -0,0 +1,60 @@
+use crate::prelude::*;
+use serde::{Serialize, Deserialize};
+
+//The main dungeon is branch 0; side branches take the IDs they have in the content database
+pub const MAIN_DUNGEON: u32 = 0;
+//Floors are told apart by a single number everywhere, so each branch gets its own block of them
+const FLOORS_PER_BRANCH: i32 = 1000;
+
+//The floor number used to tag entities and store maps for a depth in a branch.
+//Main dungeon floors are numbered by their depth.
+pub fn floor_key(branch: u32, depth: i32) -> i32 {
+    return branch as i32 * FLOORS_PER_BRANCH + depth
+}
+
+//Splits a floor key back up into its branch and depth
+pub fn split_floor_key(key: i32) -> (u32, i32) {
+    return ((key / FLOORS_PER_BRANCH) as u32, key % FLOORS_PER_BRANCH)
+}
+
+//What walls and floors look like in a branch, instead of the usual tiles
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct TilePalette {
+    pub wall: (u16, ColorPair),
+    pub floor: (u16, ColorPair)
+}
+
+//A side branch off the main dungeon. Its entrance turns up somewhere between the min and max depth,
+//and gets pinned down once a run starts.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Branch {
+    pub id: u32,
+    pub name: String,
+    pub entry_min_depth: i32,
+    pub entry_max_depth: i32,
+    pub entry_depth: i32,
+    pub floors: i32,
+    pub palette: TilePalette
+}
+impl Branch {
+    //How hard a floor in the branch is, going by how deep it sits below the main dungeon
+    pub fn difficulty(&self, depth: i32) -> i32 {
+        return self.entry_depth + depth
+    }
+
+    //Decides where each branch's entrance goes for a run. Only one branch can start on any given floor,
+    //so a branch that finds every floor in its range taken is left out.
+    pub fn place_entrances(mut branches: Vec<Branch>, rng: &mut RandomNumberGenerator) -> Vec<Branch> {
+        let mut placed: Vec<Branch> = Vec::new();
+        for mut branch in branches.drain(..) {
+            let mut depths: Vec<i32> = (branch.entry_min_depth..=branch.entry_max_depth)
+                .filter(|d| !placed.iter().any(|b| b.entry_depth == *d))
+                .collect();
+            if depths.is_empty() { continue }
+
+            branch.entry_depth = depths.remove(rng.range(0, depths.len()));
+            placed.push(branch);
+        }
+        return placed
+    }
+}
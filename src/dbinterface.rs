@@ -22,7 +22,8 @@ struct ExportedObject {
     item_stats: Option<ItemStats>,
     equip_slot: Option<EquipSlot>,
     view_range: Option<i32>,
-    initiative: Option<u8>
+    initiative: Option<u8>,
+    light_radius: Option<i32>
 }
 
 pub fn open_connection() -> Connection {
@@ -65,7 +66,8 @@ pub fn import_enemies_to_objects(conn: &Connection, table: String, where_args: O
             item_stats: None,
             equip_slot: None,
             view_range: Some(row.get("view_range")?),
-            initiative: Some(row.get("initiative")?)
+            initiative: Some(row.get("initiative")?),
+            light_radius: Some(row.get("light_radius")?)
         })
     }).ok()? {
         if let Ok(exp) = enemy {
@@ -91,6 +93,8 @@ pub fn import_enemies_to_objects(conn: &Connection, table: String, where_args: O
                 block_tile: true,
                 initiative: exp.initiative,
                 ai: Some(AIClass::new()),
+                //Anything that glows lights its surroundings in its own colour
+                light: exp.light_radius.filter(|r| *r > 0).map(|radius| LightSource { radius, color: fg }),
                 ..Default::default()
             };
             objs.push(obj);
@@ -132,7 +136,8 @@ pub fn import_items_to_objects(conn: &Connection, table: String, where_args: Opt
             },
             equip_slot: EquipSlot::match_db_string(row.get("item_slot").unwrap_or(format!("NIL"))),
             view_range: None,
-            initiative: None
+            initiative: None,
+            light_radius: None
         })
     }).ok()? {
         if let Ok(exp) = item {
@@ -157,6 +162,143 @@ pub fn import_items_to_objects(conn: &Connection, table: String, where_args: Opt
     return Some(objs)
 }
 
+//Grabs every vault that can appear at a depth, along with what its spawn markers stand for
+pub fn import_vault_templates(conn: &Connection, depth: i32) -> Option<Vec<VaultTemplate>> {
+    let mut vaults: Vec<VaultTemplate> = Vec::new();
+
+    let mut main_q = build_initial_query(conn, String::from("Vaults"), Some(format!("min_depth <= {0} AND max_depth >= {0} ORDER BY id", depth)));
+
+    for vault in main_q.query_map(params![], |row| {
+        Ok(
+            (row.get("id")?, row.get("name")?, row.get("min_depth")?, row.get("max_depth")?, row.get("chance")?, row.get("layout")?)
+        )
+    }).ok()? {
+        if let Ok((id, name, min_depth, max_depth, chance, layout)) = vault {
+            let id: u32 = id;
+            let layout: String = layout;
+            let spawns = import_vault_spawns(conn, id).unwrap_or(vec![]);
+            vaults.push(VaultTemplate::new(name, min_depth, max_depth, chance, layout.as_str(), spawns));
+        }
+    }
+
+    return Some(vaults)
+}
+
+//Grabs the level profile for a depth in a branch. When depth ranges overlap, the narrowest one wins.
+pub fn import_level_profile(conn: &Connection, branch: u32, depth: i32) -> Option<LevelProfile> {
+    let mut main_q = build_initial_query(conn, String::from("LevelProfiles"), Some(format!("branch = {0} AND min_depth <= {1} AND max_depth >= {1} ORDER BY max_depth - min_depth, id", branch, depth)));
+
+    for profile in main_q.query_map(params![], |row| {
+        Ok(
+            (row.get("generation_mode")?, row.get("floor_percent")?, row.get("walker_lifetime")?,
+             row.get("width")?, row.get("height")?, row.get("max_rooms")?,
+             row.get("spawn_table")?, row.get("enemy_density")?, row.get("item_budget")?, row.get("light_sources")?)
+        )
+    }).ok()? {
+        if let Ok((mode, floor_percent, lifetime, width, height, max_rooms, spawn_table, enemy_density, item_budget, light_sources)) = profile {
+            let mode: String = mode;
+            let carver = match (floor_percent, lifetime) {
+                (Some(floor_percent), Some(lifetime)) => Some(CarverSettings { floor_percent, lifetime }),
+                _ => None
+            };
+            let mode = match (mode.as_str(), carver) {
+                ("RandomRooms", _) => GenerationMode::RandomRooms,
+                ("Bsp", _) => GenerationMode::Bsp,
+                ("Caves", _) => GenerationMode::Caves,
+                ("DrunkardsWalk", Some(settings)) => GenerationMode::DrunkardsWalk(settings),
+                ("Dla", Some(settings)) => GenerationMode::Dla(settings),
+                _ => continue
+            };
+            let max_rooms: u32 = max_rooms;
+            return Some(LevelProfile { mode, width, height, max_rooms: max_rooms as usize, spawn_table, enemy_density, item_budget, light_sources })
+        }
+    }
+
+    return None
+}
+
+//Grabs every side branch, along with the look of its walls and floors
+pub fn import_branches(conn: &Connection) -> Option<Vec<Branch>> {
+    let mut branches: Vec<Branch> = Vec::new();
+
+    let mut main_q = build_initial_query(conn, String::from("Branches"), None);
+
+    for branch in main_q.query_map(params![], |row| {
+        Ok(
+            (row.get("id")?, row.get("name")?, row.get("entry_min_depth")?, row.get("entry_max_depth")?,
+             row.get("floors")?, row.get("wall_render_id")?, row.get("floor_render_id")?)
+        )
+    }).ok()? {
+        if let Ok((id, name, entry_min_depth, entry_max_depth, floors, wall_id, floor_id)) = branch {
+            let palette = match (import_tile_render(conn, wall_id), import_tile_render(conn, floor_id)) {
+                (Some(wall), Some(floor)) => TilePalette { wall, floor },
+                _ => continue
+            };
+            branches.push(Branch { id, name, entry_min_depth, entry_max_depth, entry_depth: entry_min_depth, floors, palette });
+        }
+    }
+
+    return Some(branches)
+}
+
+//Grabs how a run ends: its final depth, and the boss and goal item waiting there
+pub fn import_endgame(conn: &Connection) -> Option<Endgame> {
+    let mut main_q = build_initial_query(conn, String::from("Endgame"), None);
+
+    for endgame in main_q.query_map(params![], |row| {
+        Ok((row.get("final_depth")?, row.get("boss_id")?, row.get("goal_item_id")?))
+    }).ok()? {
+        if let Ok((final_depth, boss_id, goal_item_id)) = endgame {
+            return Some(Endgame { final_depth, boss_id, goal_item_id })
+        }
+    }
+
+    return None
+}
+
+fn import_tile_render(conn: &Connection, render_id: u32) -> Option<(u16, ColorPair)> {
+    let mut main_q = build_initial_query(conn, String::from("Renders"), Some(format!("id = {}", render_id)));
+
+    for render in main_q.query_map(params![], |row| {
+        Ok(
+            (row.get("glyph")?, (row.get("fg_r")?, row.get("fg_g")?, row.get("fg_b")?), (row.get("bg_r")?, row.get("bg_g")?, row.get("bg_b")?))
+        )
+    }).ok()? {
+        if let Ok((glyph, fg, bg)) = render {
+            let (fg, bg): ((u8, u8, u8), (u8, u8, u8)) = (fg, bg);
+            let fg = RGBA::from_u8(fg.0, fg.1, fg.2, 255);
+            let bg = RGBA::from_u8(bg.0, bg.1, bg.2, 255);
+            return Some((glyph, ColorPair::new(fg, bg)))
+        }
+    }
+
+    return None
+}
+
+fn import_vault_spawns(conn: &Connection, vault_id: u32) -> Option<Vec<(char, VaultSpawn)>> {
+    let mut spawns = Vec::new();
+
+    let mut main_q = build_initial_query(conn, String::from("VaultSpawns"), Some(format!("vault_id = {}", vault_id)));
+
+    for entry in main_q.query_map(params![], |row| {
+        Ok(
+            (row.get("glyph")?, row.get("enemy")?, row.get("item")?)
+        )
+    }).ok()? {
+        if let Ok((glyph, enemy, item)) = entry {
+            let glyph: String = glyph;
+            let spawn = match (enemy, item) {
+                (Some(id), _) => VaultSpawn::Enemy(id),
+                (None, Some(id)) => VaultSpawn::Item(id),
+                (None, None) => continue
+            };
+            if let Some(c) = glyph.chars().next() { spawns.push((c, spawn)) }
+        }
+    }
+
+    return Some(spawns)
+}
+
 fn build_initial_query(conn: &Connection, table: String, where_args: Option<String>) -> Statement {
     return {
         let query_str = {
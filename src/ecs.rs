@@ -0,0 +1,217 @@
+use crate::prelude::*;
+use serde::{Serialize, Deserialize};
+
+//Holds one kind of component for every entity that has it, indexed by the entity's index.
+//Each entry remembers the generation it was added for, so stale handles never see it.
+#[derive(Serialize, Deserialize)]
+pub struct Storage<T> {
+    entries: Vec<Option<(u32, T)>>
+}
+impl<T> Storage<T> {
+    pub fn new() -> Storage<T> {
+        Storage { entries: Vec::new() }
+    }
+
+    pub fn insert(&mut self, id: EntityId, component: T) {
+        let idx = id.idx as usize;
+        while self.entries.len() <= idx { self.entries.push(None) }
+        self.entries[idx] = Some((id.gen, component));
+    }
+    pub fn remove(&mut self, id: EntityId) -> Option<T> {
+        if !self.contains(id) { return None }
+        self.entries[id.idx as usize].take().map(|(_, c)| c)
+    }
+
+    pub fn contains(&self, id: EntityId) -> bool {
+        self.get(id).is_some()
+    }
+    pub fn get(&self, id: EntityId) -> Option<&T> {
+        match self.entries.get(id.idx as usize) {
+            Some(Some((gen, c))) if *gen == id.gen => Some(c),
+            _ => None
+        }
+    }
+    pub fn get_mut(&mut self, id: EntityId) -> Option<&mut T> {
+        match self.entries.get_mut(id.idx as usize) {
+            Some(Some((gen, c))) if *gen == id.gen => Some(c),
+            _ => None
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (EntityId, &T)> {
+        self.entries.iter().enumerate().filter_map(|(i, e)| {
+            e.as_ref().map(|(gen, c)| (EntityId { idx: i as u32, gen: *gen }, c))
+        })
+    }
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (EntityId, &mut T)> {
+        self.entries.iter_mut().enumerate().filter_map(|(i, e)| {
+            e.as_mut().map(|(gen, c)| (EntityId { idx: i as u32, gen: *gen }, c))
+        })
+    }
+
+    //Queries every entity that has both this component and another one
+    pub fn join<'a, U>(&'a self, other: &'a Storage<U>) -> impl Iterator<Item = (EntityId, &'a T, &'a U)> {
+        self.iter().filter_map(move |(id, a)| other.get(id).map(|b| (id, a, b)))
+    }
+    pub fn join_mut<'a, U>(&'a mut self, other: &'a Storage<U>) -> impl Iterator<Item = (EntityId, &'a mut T, &'a U)> {
+        self.iter_mut().filter_map(move |(id, a)| other.get(id).map(|b| (id, a, b)))
+    }
+}
+
+//The game world's entities and all of their components. Systems borrow just the storages they need,
+//so several can be borrowed mutably at once.
+#[derive(Serialize, Deserialize)]
+pub struct Ecs {
+    gens: Vec<u32>,
+    alive: Vec<bool>,
+    free: Vec<u32>,
+
+    pub names: Storage<String>,
+    pub tags: Storage<ActorTag>,
+    pub positions: Storage<Point>,
+    pub floors: Storage<i32>,
+    pub renders: Storage<Render>,
+    pub memories: Storage<PlayerMemory>,
+    pub viewsheds: Storage<Viewshed>,
+    pub blockers: Storage<BlocksTile>,
+    pub initiatives: Storage<u8>,
+    pub delays: Storage<u8>,
+
+    pub inventories: Storage<InInventory>,
+    pub equip_slots: Storage<EquipSlot>,
+
+    pub healths: Storage<Health>,
+    pub damages: Storage<Damage>,
+
+    pub ais: Storage<AIClass>,
+    pub item_stats: Storage<ItemStats>,
+    pub traps: Storage<Trap>,
+    pub goals: Storage<GoalItem>,
+    pub lights: Storage<LightSource>
+}
+impl Ecs {
+    pub fn new() -> Ecs {
+        Ecs {
+            gens: Vec::new(),
+            alive: Vec::new(),
+            free: Vec::new(),
+            names: Storage::new(),
+            tags: Storage::new(),
+            positions: Storage::new(),
+            floors: Storage::new(),
+            renders: Storage::new(),
+            memories: Storage::new(),
+            viewsheds: Storage::new(),
+            blockers: Storage::new(),
+            initiatives: Storage::new(),
+            delays: Storage::new(),
+            inventories: Storage::new(),
+            equip_slots: Storage::new(),
+            healths: Storage::new(),
+            damages: Storage::new(),
+            ais: Storage::new(),
+            item_stats: Storage::new(),
+            traps: Storage::new(),
+            goals: Storage::new(),
+            lights: Storage::new()
+        }
+    }
+
+    //Makes a new entity with no components, reusing the oldest free index if there is one
+    pub fn create_entity(&mut self) -> EntityId {
+        return if let Some(idx) = self.free.pop() {
+            self.gens[idx as usize] += 1;
+            self.alive[idx as usize] = true;
+            EntityId { idx, gen: self.gens[idx as usize] }
+        } else {
+            self.gens.push(0);
+            self.alive.push(true);
+            EntityId { idx: self.gens.len() as u32 - 1, gen: 0 }
+        }
+    }
+    //Breaks an object template up into components on a new entity
+    pub fn spawn(&mut self, obj: Object) -> EntityId {
+        let id = self.create_entity();
+
+        if let Some(name) = obj.name { self.names.insert(id, name) }
+        if let Some(tag) = obj.tag { self.tags.insert(id, tag) }
+        if let Some(pos) = obj.pos { self.positions.insert(id, pos) }
+        self.floors.insert(id, obj.floor);
+        if let Some(render) = obj.render { self.renders.insert(id, render) }
+        self.memories.insert(id, obj.player_mem);
+        if let Some(view) = obj.viewshed { self.viewsheds.insert(id, view) }
+        if obj.block_tile { self.blockers.insert(id, BlocksTile) }
+        if let Some(init) = obj.initiative { self.initiatives.insert(id, init) }
+        if let Some(inv) = obj.in_inventory { self.inventories.insert(id, inv) }
+        if let Some(slot) = obj.equip_slot { self.equip_slots.insert(id, slot) }
+        if let Some(health) = obj.health { self.healths.insert(id, health) }
+        if let Some(damage) = obj.damage { self.damages.insert(id, damage) }
+        if let Some(ai) = obj.ai { self.ais.insert(id, ai) }
+        if let Some(stats) = obj.item_stats { self.item_stats.insert(id, stats) }
+        if let Some(trap) = obj.trap { self.traps.insert(id, trap) }
+        if obj.goal { self.goals.insert(id, GoalItem) }
+        if let Some(light) = obj.light { self.lights.insert(id, light) }
+
+        return id
+    }
+    //Removes an entity and every component attached to it
+    pub fn despawn(&mut self, id: EntityId) {
+        if !self.is_alive(id) { return }
+
+        self.names.remove(id);
+        self.tags.remove(id);
+        self.positions.remove(id);
+        self.floors.remove(id);
+        self.renders.remove(id);
+        self.memories.remove(id);
+        self.viewsheds.remove(id);
+        self.blockers.remove(id);
+        self.initiatives.remove(id);
+        self.delays.remove(id);
+        self.inventories.remove(id);
+        self.equip_slots.remove(id);
+        self.healths.remove(id);
+        self.damages.remove(id);
+        self.ais.remove(id);
+        self.item_stats.remove(id);
+        self.traps.remove(id);
+        self.goals.remove(id);
+        self.lights.remove(id);
+
+        self.alive[id.idx as usize] = false;
+        self.free.insert(0, id.idx);
+    }
+
+    pub fn is_alive(&self, id: EntityId) -> bool {
+        let idx = id.idx as usize;
+        idx < self.gens.len() && self.alive[idx] && self.gens[idx] == id.gen
+    }
+    pub fn entities(&self) -> Vec<EntityId> {
+        self.gens.iter().enumerate()
+            .filter(|(i, _)| self.alive[*i])
+            .map(|(i, gen)| EntityId { idx: i as u32, gen: *gen })
+            .collect()
+    }
+
+    //Shorthands for components that are looked up all over the place
+    pub fn get_name(&self, id: EntityId) -> String {
+        self.names.get(id).cloned().unwrap_or(format!("NIL"))
+    }
+    pub fn get_fg(&self, id: EntityId) -> RGBA {
+        self.renders.get(id).unwrap_or(&Render::nil_render()).color.fg
+    }
+    pub fn get_floor(&self, id: EntityId) -> i32 {
+        *self.floors.get(id).unwrap_or(&0)
+    }
+    //Hidden traps aren't drawn or remembered until they're found
+    pub fn is_hidden(&self, id: EntityId) -> bool {
+        self.traps.get(id).map_or(false, |trap| !trap.revealed)
+    }
+    //Uses up one of the turns an entity has to sit out, returning false if it had none left
+    pub fn take_delay(&mut self, id: EntityId) -> bool {
+        return match self.delays.get_mut(id) {
+            Some(turns) if *turns > 0 => { *turns -= 1; true },
+            _ => false
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use crate::prelude::*;
+
+//How deep runs went before the final depth came from the content database
+pub const DEFAULT_FINAL_DEPTH: i32 = 10;
+
+//How a run is won: the floor the main dungeon bottoms out on, the boss waiting there and the item it guards.
+//These come from the content database so the length of a run can be tuned without touching code.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Endgame {
+    pub final_depth: i32,
+    pub boss_id: u32,
+    pub goal_item_id: u32
+}
+impl Endgame {
+    pub fn load() -> Endgame {
+        let conn = open_connection();
+        let endgame = import_endgame(&conn).expect("Failed to import the endgame from the database.");
+        conn.close().expect("Connection to SQLite DB failed to close.");
+
+        return endgame
+    }
+}
+
+//Returns the goal item an entity is carrying, if it has it
+pub fn carried_goal_item(ecs: &Ecs, owner: EntityId) -> Option<EntityId> {
+    for (id, inv, _) in ecs.inventories.join(&ecs.goals) {
+        if inv.owner_id == owner { return Some(id) }
+    }
+    return None
+}
+
+//Everything the victory screen has to say about a finished run
+pub struct RunSummary {
+    pub seed: u64,
+    pub turns: u32,
+    pub deepest: i32,
+    pub floors_explored: usize,
+    pub branches_explored: Vec<String>,
+    pub health: (i32, i32),
+    pub goal: Option<String>,
+    pub permadeath: bool
+}
+impl RunSummary {
+    pub fn from_world(world: &World) -> RunSummary {
+        let mut visited: Vec<i32> = world.stored_maps.keys().cloned().collect();
+        visited.push(world.floor());
+
+        let deepest = visited.iter()
+            .filter(|k| split_floor_key(**k).0 == MAIN_DUNGEON)
+            .map(|k| split_floor_key(*k).1)
+            .max()
+            .unwrap_or(world.depth);
+        let branches_explored = world.branches.iter()
+            .filter(|b| visited.iter().any(|k| split_floor_key(*k).0 == b.id))
+            .map(|b| b.name.clone())
+            .collect();
+        let health = match world.ecs.healths.get(PLAYER) {
+            Some(health) => (health.current, health.max),
+            None => (0, 0)
+        };
+
+        RunSummary {
+            seed: world.seed,
+            turns: world.turns,
+            deepest,
+            floors_explored: visited.len(),
+            branches_explored,
+            health,
+            goal: carried_goal_item(&world.ecs, PLAYER).map(|id| world.ecs.get_name(id)),
+            permadeath: world.permadeath
+        }
+    }
+}
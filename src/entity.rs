@@ -0,0 +1,12 @@
+use serde::{Serialize, Deserialize};
+
+//A handle to an entity in the world. The generation is bumped every time an index is reused,
+//so a handle to something that has since been despawned never points at whatever replaced it.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+pub struct EntityId {
+    pub idx: u32,
+    pub gen: u32
+}
+
+//The player is always the first entity spawned into a fresh world, and is never despawned
+pub const PLAYER: EntityId = EntityId { idx: 0, gen: 0 };
@@ -0,0 +1,366 @@
+use crate::prelude::*;
+use serde::{Serialize, Deserialize};
+
+//Falling through a chasm hurts for 1d this much
+const CHASM_FALL_DAMAGE: i32 = 6;
+
+#[derive(PartialEq)]
+pub enum TurnState { Player, AI, GameOver, Victory }
+
+//Every turn-taking thing the player can do; these are what get written to replay files
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Actions {
+    MoveUp,MoveDown,MoveLeft,MoveRight,
+    MoveUpLeft,MoveUpRight,MoveDownLeft,MoveDownRight,
+    TryPickUp,
+    TryGoDown,
+    TryGoUp,
+    Wait,
+    UseItem(EntityId, ItemUsage),
+    TryCloseDoor,
+    Search,
+    TryDisarm,
+}
+
+//A running game and everything needed to play out its turns, with no console attached
+pub struct Game {
+    pub world: World,
+    pub logs: LogBuffer,
+    pub turn_state: TurnState,
+    pub proc: bool,
+    pub passed: bool,
+    pub gameover: bool,
+    pub recorder: Option<ReplayRecorder>
+}
+impl Game {
+    pub fn empty() -> Game {
+        Game {
+            world: World::empty(),
+            logs: LogBuffer::new(),
+            turn_state: TurnState::Player,
+            proc: true,
+            passed: false,
+            gameover: false,
+            recorder: None
+        }
+    }
+    pub fn new(seed: u64, permadeath: bool) -> Game {
+        let mut game = Game::empty();
+        game.world = World::new_game(seed);
+        game.world.permadeath = permadeath;
+
+        game.logs.update_logs(LogMessage::new()
+            .add_part("Your adventure begins now. ", ColorPair::new(WHITE,GREY10))
+            .add_part("Prepare to die...", ColorPair::new(RED, GREY10))
+        );
+        return game
+    }
+    //Wraps an already built world, e.g. one that was just loaded from a save
+    pub fn from_world(world: World) -> Game {
+        let mut game = Game::empty();
+        game.world = world;
+        return game
+    }
+
+    //Plays out a whole turn: the player's action, then everything that happens in response to it
+    pub fn advance_turn(&mut self, action: Actions) {
+        self.process_action(action);
+        self.exec_all_systems();
+    }
+
+    pub fn process_action(&mut self, action: Actions) {
+        if let Some(log) = self.world.action_log.as_mut() { log.push(action) }
+        //Recording stops if the replay file can't be written to, rather than leaving a replay with gaps in it
+        if let Some(recorder) = self.recorder.as_mut() {
+            if let Err(e) = recorder.record(&action) {
+                self.logs.update_logs(LogMessage::new()
+                    .add_part(format!("{} Recording has stopped.", e), ColorPair::new(GREY65, GREY10))
+                );
+                self.recorder = None;
+            }
+        }
+
+        let action_result: bool = match action {
+            Actions::Wait => {
+                self.regen_player_ok();
+                true
+            },
+
+            Actions::MoveLeft => self.try_move_player(DL_LEFT),
+            Actions::MoveRight => self.try_move_player(DL_RIGHT),
+            Actions::MoveUp => self.try_move_player(DL_UP),
+            Actions::MoveDown => self.try_move_player(DL_DOWN),
+
+            Actions::MoveUpLeft => self.try_move_player(DL_UP + DL_LEFT),
+            Actions::MoveUpRight => self.try_move_player(DL_UP + DL_RIGHT),
+            Actions::MoveDownLeft => self.try_move_player(DL_DOWN + DL_LEFT),
+            Actions::MoveDownRight => self.try_move_player(DL_DOWN + DL_RIGHT),
+
+            Actions::TryPickUp => {
+                let had_goal = carried_goal_item(&self.world.ecs, PLAYER).is_some();
+                try_pick_up(&mut self.world.ecs, PLAYER, &mut self.logs, true);
+                //Nothing is won until the goal item has been carried back out
+                if !had_goal && carried_goal_item(&self.world.ecs, PLAYER).is_some() {
+                    self.logs.update_logs(LogMessage::new()
+                        .add_part("Now to escape back up the stairs with it!", ColorPair::new(GOLD, GREY10))
+                    );
+                }
+                true
+            },
+
+            Actions::TryGoDown => self.try_go_downstairs(),
+            Actions::TryGoUp => self.try_go_upstairs(),
+
+            Actions::UseItem(id, usage) => use_item(&mut self.world.ecs, id, usage, &mut self.logs, &mut self.world.rng),
+
+            Actions::TryCloseDoor => self.try_close_door_player(),
+
+            Actions::Search => {
+                let world = &mut self.world;
+                let traps = search_for_traps(&mut world.ecs, PLAYER, &mut self.logs, &mut world.rng);
+                let secrets = search_for_secrets(&mut world.ecs, PLAYER, &mut world.active_map, &mut self.logs, &mut world.rng);
+                if traps + secrets == 0 {
+                    self.logs.update_logs(LogMessage::new()
+                        .add_part("You search the area, but find nothing.", ColorPair::new(GREY65, GREY10))
+                    );
+                }
+                true
+            },
+            Actions::TryDisarm => try_disarm_trap(&mut self.world.ecs, PLAYER, &mut self.logs, &mut self.world.rng)
+        };
+        self.proc = true;
+        if action_result { self.passed = true; }
+    }
+
+    pub fn exec_all_systems(&mut self) {
+        if self.proc {
+            let floor = self.world.floor();
+            process_fov(&mut self.world.ecs, &mut self.world.active_map, floor);
+            let fell = self.resolve_traps();
+            let world = &mut self.world;
+            proc_all_wounds(&mut world.ecs, &mut self.logs, &mut self.gameover);
+            //Blocked tiles are refreshed after wounds, so anything just killed stops blocking straight away
+            update_blocked_tiles(&world.ecs, &mut world.active_map, floor);
+            if fell && !self.gameover { self.fall_to_next_floor("through the trapdoor") }
+
+            //Check if the player's turn was passed
+            if self.passed {
+                self.turn_state = TurnState::AI;
+                self.passed = false;
+            }
+
+            //Run any stuff for the AI if it's the AI's turn. A player held up by the terrain sits out
+            //more turns, and everything else keeps going in the meantime.
+            if self.turn_state == TurnState::AI {
+                self.pass_world_turn();
+                while !self.gameover && self.world.ecs.take_delay(PLAYER) { self.pass_world_turn() }
+                self.turn_state = TurnState::Player;
+            }
+
+            let world = &mut self.world;
+            update_player_memory(&mut world.ecs, &world.active_map);
+
+            //Set the turn state on a game over event.
+            if self.gameover {
+                self.logs.update_logs(LogMessage::new()
+                    .add_part(format!("You have perished on {}.", world.level_name(world.branch, world.depth)), ColorPair::new(BLACK, RED))
+                );
+                self.turn_state = TurnState::GameOver;
+                self.gameover = false;
+                self.end_run();
+            }
+
+            self.proc = false;
+        }
+    }
+
+    //Wraps up a run that has been won or lost. A finished permadeath run's save is gone for good.
+    fn end_run(&mut self) {
+        let world = &self.world;
+        if world.permadeath {
+            if let Some(slot) = world.save_slot {
+                if slot_exists(slot) {
+                    if let Err(e) = delete_save(slot) {
+                        self.logs.update_logs(LogMessage::new()
+                            .add_part(format!("Could not remove this run's save. {}", e), ColorPair::new(GREY65, GREY10))
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    //Everything that happens once the player's turn has passed
+    fn pass_world_turn(&mut self) {
+        let world = &mut self.world;
+        let floor = world.floor();
+        world.turns += 1;
+        process_fov(&mut world.ecs, &mut world.active_map, floor);
+        proc_regen(&mut world.ecs);
+
+        process_ai(&mut world.ecs, &mut world.active_map, floor, &mut world.rng);
+        let trapdoor = self.resolve_traps();
+        let world = &mut self.world;
+        process_fov(&mut world.ecs, &mut world.active_map, floor);
+        spot_traps(&mut world.ecs, &mut self.logs, &mut world.rng);
+        let chasm = proc_hazards(&mut world.ecs, &world.active_map, floor, &mut self.logs, &mut world.rng);
+        proc_all_wounds(&mut world.ecs, &mut self.logs, &mut self.gameover);
+
+        if !self.gameover {
+            if trapdoor { self.fall_to_next_floor("through the trapdoor") }
+            else if chasm { self.fall_to_next_floor("into the chasm") }
+        }
+    }
+
+    //Sets off whatever traps were sprung since the last check, keeping the camera on a player that got moved.
+    //Returns true if the player went through a trapdoor.
+    fn resolve_traps(&mut self) -> bool {
+        let world = &mut self.world;
+        let floor = world.floor();
+        let fell = proc_traps(&mut world.ecs, &world.active_map, floor, &mut self.logs, &mut world.rng);
+        if let Some(pos) = world.ecs.positions.get(PLAYER) { world.camera.move_camera(*pos) }
+        return fell
+    }
+
+    //Drops the player down to the next floor, hurting them on the landing
+    fn fall_to_next_floor(&mut self, cause: &str) {
+        self.logs.update_logs(LogMessage::new()
+            .add_part(format!("You fall {}, landing on {}!", cause, self.world.level_name(self.world.branch, self.world.depth + 1)), ColorPair::new(GREY13, WHITE))
+        );
+        let world = &mut self.world;
+        world.descend_to_next();
+        world.ecs.delays.remove(PLAYER);
+        let floor = world.floor();
+
+        let landing = world.rng.roll_dice(1, CHASM_FALL_DAMAGE);
+        world.ecs.healths.get_mut(PLAYER).unwrap().wounds.push(landing);
+        proc_all_wounds(&mut world.ecs, &mut self.logs, &mut self.gameover);
+        process_fov(&mut world.ecs, &mut world.active_map, floor);
+        update_blocked_tiles(&world.ecs, &mut world.active_map, floor);
+    }
+
+    //Attempts to move the player to another tile, opening any closed door in the way instead
+    fn try_move_player(&mut self, delta: Point) -> bool {
+        let map = &mut self.world.active_map;
+        let camera = &mut self.world.camera;
+        let ecs = &mut self.world.ecs;
+
+        let mut dest = *ecs.positions.get(PLAYER).unwrap() + delta;
+        if try_open_door(ecs, PLAYER, dest, map) {
+            self.logs.update_logs(LogMessage::new()
+                .add_part("You open the door.", ColorPair::new(GREY65, GREY10))
+            );
+            return true
+        }
+
+        try_move(ecs, PLAYER, dest, map);
+        let pos = *ecs.positions.get(PLAYER).unwrap();
+        camera.move_camera(pos);
+
+        return if pos == dest { true } else { self.try_attack_player(&mut dest) }
+    }
+
+    //Attempts to attack something
+    fn try_attack_player(&mut self, dest: &mut Point) -> bool {
+        let ecs = &mut self.world.ecs;
+        let floor = ecs.get_floor(PLAYER);
+        let mut target: Option<EntityId> = None;
+
+        for (id, pos, tag) in ecs.positions.join(&ecs.tags) {
+            if pos == dest && ecs.healths.contains(id) && ecs.get_floor(id) == floor && tag == &ActorTag::Enemy {
+                target = Some(id);
+            }
+        }
+
+        return match target {
+            Some(tgt) => try_attack(ecs, PLAYER, tgt, &mut self.world.rng),
+            None => false
+        }
+    }
+
+    //Attempts to close the doors around the player
+    fn try_close_door_player(&mut self) -> bool {
+        return if try_close_doors(&mut self.world.ecs, PLAYER, &mut self.world.active_map) {
+            self.logs.update_logs(LogMessage::new()
+                .add_part("You close the door.", ColorPair::new(GREY65, GREY10))
+            );
+            true
+        } else {
+            self.logs.update_logs(LogMessage::new()
+                .add_part("No open door to close!", ColorPair::new(GREY65, GREY10))
+            );
+            false
+        }
+    }
+
+    //Attempts to walk down a downward staircase, or into a side branch
+    fn try_go_downstairs(&mut self) -> bool {
+        let map = &self.world.active_map;
+        let pos = *self.world.ecs.positions.get(PLAYER).unwrap();
+        let world = &self.world;
+
+        return if map.tiles[map.index(pos.x, pos.y)] == TileClass::DownStair {
+            self.logs.update_logs(LogMessage::new()
+                .add_part(format!("Descending to {}...", world.level_name(world.branch, world.depth + 1)), ColorPair::new(GREY13, WHITE))
+            );
+            self.world.descend_to_next();
+            true
+        } else if map.tiles[map.index(pos.x, pos.y)] == TileClass::BranchStair {
+            let branch = world.branches.iter().find(|b| b.entry_depth == world.depth).map_or(MAIN_DUNGEON, |b| b.id);
+            self.logs.update_logs(LogMessage::new()
+                .add_part(format!("Descending to {}...", world.level_name(branch, 1)), ColorPair::new(GREY13, WHITE))
+            );
+            self.world.enter_branch();
+            true
+        } else {
+            self.logs.update_logs(LogMessage::new()
+                .add_part("No stairs to descend!", ColorPair::new(GREY65, GREY10))
+            );
+            false
+        }
+    }
+
+    //Attempts to walk up an upward staircase, back to the floor above as it was left
+    fn try_go_upstairs(&mut self) -> bool {
+        let map = &self.world.active_map;
+        let pos = *self.world.ecs.positions.get(PLAYER).unwrap();
+
+        return if map.tiles[map.index(pos.x, pos.y)] == TileClass::UpStair {
+            let world = &self.world;
+            //Carrying the goal item back up out of the final floor wins the run, before anything gets another go
+            if world.branch == MAIN_DUNGEON && world.depth >= world.final_depth {
+                if let Some(goal) = carried_goal_item(&world.ecs, PLAYER) {
+                    self.logs.update_logs(LogMessage::new()
+                        .add_part(format!("You escape the depths with the {}!", world.ecs.get_name(goal)), ColorPair::new(BLACK, GOLD))
+                        .add_part("The depths have been conquered.", ColorPair::new(GOLD, GREY10))
+                    );
+                    self.turn_state = TurnState::Victory;
+                    self.end_run();
+                    return false
+                }
+            }
+
+            let above = match world.current_branch() {
+                Some(branch) if world.depth == 1 => world.level_name(MAIN_DUNGEON, branch.entry_depth),
+                _ => world.level_name(world.branch, world.depth - 1)
+            };
+            self.logs.update_logs(LogMessage::new()
+                .add_part(format!("Ascending to {}...", above), ColorPair::new(GREY13, WHITE))
+            );
+            self.world.ascend_to_previous();
+            true
+        } else {
+            self.logs.update_logs(LogMessage::new()
+                .add_part("No stairs to ascend!", ColorPair::new(GREY65, GREY10))
+            );
+            false
+        }
+    }
+
+    //Flags the player as okay to start regenerating health
+    fn regen_player_ok(&mut self) {
+        if let Some(health) = self.world.ecs.healths.get_mut(PLAYER) {
+            health.set_regen_valid(true);
+        }
+    }
+}
@@ -1,20 +1,16 @@
 use crate::prelude::*;
 
-pub enum Actions {
-    MoveUp,MoveDown,MoveLeft,MoveRight,
-    MoveUpLeft,MoveUpRight,MoveDownLeft,MoveDownRight,
-    TryPickUp,
-    TryGoDown,
-    Wait,
-}
-
 //Grabs the player's keypresses
 pub fn player_input(gs: &mut State, con: &BTerm) {
     match gs.con_status {
         ContextStatus::InGame => ingame_input(gs, con),
         ContextStatus::InventoryOpen => inventory_input(gs, con, gs.inv.as_ref().unwrap().submenu.is_some()),
         ContextStatus::MainMenu |
-        ContextStatus::PauseMenu => menu_input(gs, con),
+        ContextStatus::PauseMenu |
+        ContextStatus::SlotMenu => menu_input(gs, con),
+        ContextStatus::SeedEntry => seed_entry_input(gs, con),
+        //A won run is over, so the victory screen is left the same way as a game over
+        ContextStatus::Victory => {}
     }
 }
 
@@ -23,8 +19,8 @@ pub fn game_over_input(gs: &mut State, con: &BTerm) {
         match key {
             VirtualKeyCode::Return | VirtualKeyCode::R => {
                 gs.con_status = ContextStatus::MainMenu;
-                gs.menu = Some(Menu::main_menu());
-                gs.turn_state = TurnState::Player;
+                gs.menu = Some(Menu::main_menu(gs.permadeath));
+                gs.game.turn_state = TurnState::Player;
                 gs.refresh_con = true;
             },
             _ => {}
@@ -57,13 +53,19 @@ fn ingame_input(gs: &mut State, con: &BTerm) {
                 => process_action(gs, Actions::Wait),
 
             VirtualKeyCode::Escape => {
-                gs.menu = Some(Menu::pause_menu());
+                gs.menu = Some(Menu::pause_menu(gs.game.world.permadeath));
                 gs.con_status = ContextStatus::PauseMenu;
                 gs.refresh_con = true;
             },
 
             VirtualKeyCode::G
                 => process_action(gs, Actions::TryPickUp),
+            VirtualKeyCode::C
+                => process_action(gs, Actions::TryCloseDoor),
+            VirtualKeyCode::S
+                => process_action(gs, Actions::Search),
+            VirtualKeyCode::D
+                => process_action(gs, Actions::TryDisarm),
 
             VirtualKeyCode::Period => {
                 if con.shift || con.alt {
@@ -73,9 +75,14 @@ fn ingame_input(gs: &mut State, con: &BTerm) {
                     process_action(gs, Actions::Wait)
                 }
             }
+            VirtualKeyCode::Comma => {
+                if con.shift || con.alt {
+                    process_action(gs, Actions::TryGoUp)
+                }
+            }
 
             VirtualKeyCode::I => {
-                gs.inv = Some(InventoryMenu::new(&gs.world.objects));
+                gs.inv = Some(InventoryMenu::new(&gs.game.world.ecs));
                 gs.con_status = ContextStatus::InventoryOpen;
                 gs.refresh_con = true;
             },
@@ -83,12 +90,13 @@ fn ingame_input(gs: &mut State, con: &BTerm) {
             //CHEATYFACE MODE
             /*
             VirtualKeyCode::Grave => {
-                for obj in gs.world.objects.iter() {
+                let ecs = &gs.game.world.ecs;
+                for id in ecs.entities().into_iter() {
                     println!("{} is at {},{}, with icon {}",
-                             obj.name.as_ref().unwrap_or(&"NIL".to_string()),
-                             obj.pos.as_ref().unwrap_or(&Point::zero()).x,
-                             obj.pos.as_ref().unwrap_or(&Point::zero()).y,
-                             to_char(obj.render.unwrap_or(Render { glyph: 1, color: ColorPair::new(BLACK, BLACK), order: 0 }).glyph as u8).to_string()
+                             ecs.get_name(id),
+                             ecs.positions.get(id).unwrap_or(&Point::zero()).x,
+                             ecs.positions.get(id).unwrap_or(&Point::zero()).y,
+                             to_char(ecs.renders.get(id).unwrap_or(&Render { glyph: 1, color: ColorPair::new(BLACK, BLACK), order: 0 }).glyph as u8).to_string()
                     );
                 }
             },
@@ -182,16 +190,10 @@ fn sm_move_select(gs: &mut State, updown: UpDown) {
     }
 }
 fn sm_trigger_select(gs: &mut State) {
-    let sm = gs.inv.as_mut().unwrap().submenu.as_mut().unwrap();
-    let objs = &mut gs.world.objects;
-    let logs = &mut gs.logs;
-    let rng = &mut gs.world.rng;
-    let pass = &mut gs.passed;
-
-    sm.process_selection(objs, logs, rng, pass);
+    let action = gs.inv.as_ref().unwrap().submenu.as_ref().unwrap().get_action();
 
     inv_clear(gs);
-    gs.proc = true;
+    process_action(gs, action);
 }
 
 fn inv_clear(gs: &mut State) {
@@ -214,107 +216,69 @@ fn menu_input(gs: &mut State, con: &BTerm) {
                 => gs.menu.as_mut().unwrap().cycle_selection_down(),
             VirtualKeyCode::Return
                 => gs.menu.as_mut().unwrap().process_selection(),
-            VirtualKeyCode::Escape
-                => { if gs.con_status == ContextStatus::PauseMenu { gs.con_status = ContextStatus::InGame } },
+            VirtualKeyCode::Escape => {
+                match gs.con_status {
+                    ContextStatus::PauseMenu => gs.con_status = ContextStatus::InGame,
+                    ContextStatus::SlotMenu => gs.menu.as_mut().unwrap().processed_selection = Some(MenuSelection::Back),
+                    _ => {}
+                }
+            },
+            VirtualKeyCode::D | VirtualKeyCode::Delete
+                => { if gs.con_status == ContextStatus::SlotMenu { gs.menu.as_mut().unwrap().process_delete() } },
             _ => {}
         }
         gs.refresh_con = true;
     }
 }
 
-fn process_action(gs: &mut State, action: Actions) {
-    let action_result: bool = match action {
-        Actions::Wait => {
-            regen_player_ok(gs);
-            true
-        },
-
-        Actions::MoveLeft => try_move_player(gs, DL_LEFT),
-        Actions::MoveRight => try_move_player(gs, DL_RIGHT),
-        Actions::MoveUp => try_move_player(gs, DL_UP),
-        Actions::MoveDown => try_move_player(gs, DL_DOWN),
-
-        Actions::MoveUpLeft => try_move_player(gs, DL_UP + DL_LEFT),
-        Actions::MoveUpRight => try_move_player(gs, DL_UP + DL_RIGHT),
-        Actions::MoveDownLeft => try_move_player(gs, DL_DOWN + DL_LEFT),
-        Actions::MoveDownRight => try_move_player(gs, DL_DOWN + DL_RIGHT),
-
-        Actions::TryPickUp => { try_pick_up(&mut gs.world.objects, 0, &mut gs.logs, true); true },
-
-        Actions::TryGoDown => try_go_downstairs(gs)
-    };
-    gs.refresh_con = true;
-    gs.proc = true;
-    if action_result { gs.passed = true; }
-}
-
-//Attempts to move the player to another tile
-fn try_move_player(gs: &mut State, delta: Point) -> bool {
-    let map = &gs.world.active_map;
-    let camera = &mut gs.world.camera;
-    let player = &mut gs.world.objects[0];
-
-    let mut dest = player.pos.unwrap() + delta;
-
-    player.try_move(dest, map);
-    camera.move_camera(player.pos.unwrap());
-
-    return if player.pos.unwrap() == dest { true } else { try_attack_player(gs, &mut dest) }
-}
-
-//Attempts to attack something
-fn try_attack_player(gs: &mut State, dest: &mut Point) -> bool {
-    let (player, all) = gs.world.objects.split_at_mut(1);
-    let mut target: Option<&mut Object> = None;
+//Seeds are plain numbers, so only digit keys are accepted
+const MAX_SEED_DIGITS: usize = 18;
+fn seed_entry_input(gs: &mut State, con: &BTerm) {
+    if let Some(key) = con.key {
+        let digit = match key {
+            VirtualKeyCode::Key0 | VirtualKeyCode::Numpad0 => Some('0'),
+            VirtualKeyCode::Key1 | VirtualKeyCode::Numpad1 => Some('1'),
+            VirtualKeyCode::Key2 | VirtualKeyCode::Numpad2 => Some('2'),
+            VirtualKeyCode::Key3 | VirtualKeyCode::Numpad3 => Some('3'),
+            VirtualKeyCode::Key4 | VirtualKeyCode::Numpad4 => Some('4'),
+            VirtualKeyCode::Key5 | VirtualKeyCode::Numpad5 => Some('5'),
+            VirtualKeyCode::Key6 | VirtualKeyCode::Numpad6 => Some('6'),
+            VirtualKeyCode::Key7 | VirtualKeyCode::Numpad7 => Some('7'),
+            VirtualKeyCode::Key8 | VirtualKeyCode::Numpad8 => Some('8'),
+            VirtualKeyCode::Key9 | VirtualKeyCode::Numpad9 => Some('9'),
+            _ => None
+        };
 
-    for obj in all.iter_mut() {
-        if let Object { pos: Some(pos), tag: Some(tag), health: Some(_), .. } = obj {
-            if pos == dest && obj.floor == player[0].floor && tag == &mut ActorTag::Enemy {
-                target = Some(obj);
+        match key {
+            VirtualKeyCode::Back => { gs.seed_input.pop(); },
+            VirtualKeyCode::Escape => {
+                gs.menu = Some(Menu::main_menu(gs.permadeath));
+                gs.con_status = ContextStatus::MainMenu;
+            },
+            VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => {
+                let seed = gs.seed_input.parse::<u64>().unwrap_or_else(|_| RandomNumberGenerator::new().next_u64());
+                gs.start_new_game(seed, gs.permadeath);
+            },
+            _ => {
+                if let Some(c) = digit {
+                    if gs.seed_input.len() < MAX_SEED_DIGITS { gs.seed_input.push(c) }
+                }
             }
         }
-    }
-
-    return if let Some(tgt) = target {
-        player[0].try_attack(tgt, &mut gs.world.rng);
-        true
-    } else {
-        false
-    }
-}
-
-//Attempts to walk down a downward staircase
-fn try_go_downstairs(gs: &mut State) -> bool {
-    let map = &gs.world.active_map;
-    let player = &gs.world.objects[0];
-
-    let pos = player.pos.unwrap();
-    return if map.tiles[map.index(pos.x, pos.y)] == TileClass::DownStair {
-        gs.logs.update_logs(LogMessage::new()
-            .add_part(format!("Descending to level {}...", gs.world.depth + 1), ColorPair::new(GREY13, WHITE))
-        );
-        gs.world.descend_to_next();
-        true
-    } else {
-        gs.logs.update_logs(LogMessage::new()
-            .add_part("No stairs to descend!", ColorPair::new(GREY65, GREY10))
-        );
-        false
+        gs.refresh_con = true;
     }
 }
 
-//Flags the player as okay to start regenerating health
-fn regen_player_ok(gs: &mut State) {
-    let player = &mut gs.world.objects[0];
-    if let Some(health) = &mut player.health {
-        health.set_regen_valid(true);
-    }
+//Hands an action to the game core, then flags the console for a redraw
+fn process_action(gs: &mut State, action: Actions) {
+    gs.game.process_action(action);
+    gs.refresh_con = true;
 }
 
 fn test_give_potion(gs: &mut State) {
-    give_items(&mut gs.world.objects, 0, vec![2]);
+    give_items(&mut gs.game.world.ecs, PLAYER, vec![2]);
     console::log("Giving the player a Potion!");
     gs.refresh_con = true;
-    gs.proc = true;
-    gs.passed = true;
+    gs.game.proc = true;
+    gs.game.passed = true;
 }
\ No newline at end of file
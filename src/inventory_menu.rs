@@ -0,0 +1,146 @@
+use crate::prelude::*;
+use std::cmp::min;
+
+pub struct InventoryMenu {
+    pub submenu: Option<InventorySubMenu>,
+    pub items: Vec<ItemInfo>,
+    pub selection: usize
+}
+pub struct InventorySubMenu {
+    pub info: ItemInfo,
+    pub opts: Vec<ItemUsage>,
+    pub selection: usize
+}
+#[derive(Clone)]
+pub struct ItemInfo {
+    pub obj_id: EntityId,
+    pub name: String,
+    pub render: Render,
+    pub stats: ItemStats
+}
+
+impl InventoryMenu {
+    pub fn new(ecs: &Ecs) -> InventoryMenu {
+        let mut menu = InventoryMenu {..Default::default()};
+        menu.populate_items(ecs);
+        return menu
+    }
+    pub fn populate_items(&mut self, ecs: &Ecs) {
+        for (i, inv, stats) in ecs.inventories.join(&ecs.item_stats) {
+            if inv.owner_id == PLAYER {
+                let info = ItemInfo {
+                    obj_id: i,
+                    name: ecs.names.get(i).unwrap().to_owned(),
+                    render: *ecs.renders.get(i).unwrap(),
+                    stats: stats.clone()
+                };
+                self.items.push(info);
+            }
+        }
+    }
+    pub fn process_selection(&mut self) {
+        return if self.items.len() <= 0 {}
+        else { self.submenu = Some(InventorySubMenu::new(self.items[self.selection].clone())); }
+    }
+    pub fn move_selection_up(&mut self) {
+        if self.items.len() <= 0 { return }
+        if self.selection as i16 - 1 < 0 { self.selection = self.items.len() - 1 }
+        else { self.selection -= 1 }
+    }
+    pub fn move_selection_down(&mut self) {
+        if self.items.len() <= 0 { return }
+        if self.selection + 1 >= self.items.len() { self.selection = 0 }
+        else { self.selection += 1 }
+    }
+}
+impl Default for InventoryMenu {
+    fn default() -> InventoryMenu {
+        InventoryMenu {
+            submenu: None,
+            items: Vec::new(),
+            selection: 0
+        }
+    }
+}
+
+impl InventorySubMenu {
+    pub fn new(info: ItemInfo) -> InventorySubMenu {
+        let opts = info.stats.usages.to_vec();
+        InventorySubMenu {
+            info,
+            opts,
+            selection: 0
+        }
+    }
+
+    pub fn move_selection_up(&mut self) {
+        if self.opts.len() <= 0 { return }
+        if self.selection as i16 - 1 < 0 { self.selection = self.opts.len() - 1 }
+        else { self.selection -= 1 }
+    }
+    pub fn move_selection_down(&mut self) {
+        if self.opts.len() <= 0 { return }
+        if self.selection + 1 >= self.opts.len() { self.selection = 0 }
+        else { self.selection += 1 }
+    }
+    //Item usage is routed through the action pipeline so it can be recorded like any other player action
+    pub fn get_action(&self) -> Actions {
+        Actions::UseItem(self.info.obj_id, self.opts[self.selection])
+    }
+}
+
+//Inventory menu rendering
+pub fn batch_inventory_menu(menu: &mut InventoryMenu, ecs: &Ecs) {
+    let mut uibatch = DrawBatch::new();
+    let mut textbatch = DrawBatch::new();
+    uibatch.target(OBJ_LAYER);
+    textbatch.target(TXT_LAYER);
+
+    let menubox = Rect::with_size(2, 2, CONSOLE_W - UI_CUTOFF.x - 4, min(menu.items.len() as i32 + 1, 18));
+    uibatch.draw_double_box(menubox, ColorPair::new(GREY75, BLACK));
+    textbatch.print(Point::new(8, 2), "Inventory");
+    textbatch.print_color(Point::new(8, 2 + menubox.height()), "ESC to close", ColorPair::new(GOLD4, BLACK));
+
+    let mut y = menubox.y1 + 1;
+    let mut ofs: u16 = 0;
+    for (i, item) in menu.items.iter().enumerate() {
+        let line_color = match i == menu.selection {
+            false => {ColorPair::new(WHITE,BLACK)},
+            true => {ColorPair::new(BLACK,YELLOW)}
+        };
+        if y < menubox.y2 {
+            uibatch.set(Point::new(menubox.x1 + 5, y), *&item.render.color, item.render.glyph);
+
+            //Sets the letter to display next to the item
+            uibatch.set(Point::new(menubox.x1 + 1, y), ColorPair::new(WHITE, BLACK), 40);
+            uibatch.set(Point::new(menubox.x1 + 2, y), ColorPair::new(GOLD2, BLACK), 97 + ofs);
+            uibatch.set(Point::new(menubox.x1 + 3, y), ColorPair::new(WHITE, BLACK), 41);
+
+            textbatch.print_color(Point::new(menubox.x1 * 2 + 14, y), &item.name, line_color);
+            y += 1;
+            ofs += 1;
+        }
+    }
+
+    if let Some(sub) = &mut menu.submenu {
+        let smbox = Rect::with_size(CONSOLE_W - UI_CUTOFF.x - 25, 2, 24, 12);
+        uibatch.draw_double_box(smbox, ColorPair::new(GREY75, BLACK));
+        textbatch.print(Point::new((smbox.x1 + 1) * 2, smbox.y1 + 1), &sub.info.name);
+
+        let mut ypos = smbox.y1 + 3;
+        for (i, act) in sub.opts.iter().enumerate() {
+            let select_color = match i == sub.selection {
+                false => {ColorPair::new(WHITE,BLACK)},
+                true => {ColorPair::new(BLACK,YELLOW)}
+            };
+            let equipped =
+                if let Some(stats) = ecs.item_stats.get(sub.info.obj_id) { stats.equipped }
+                else { false };
+            textbatch.print_color(Point::new((smbox.x1 + 1) * 2, ypos), act.get_name(equipped), select_color);
+            ypos += 1;
+        }
+    }
+
+    uibatch.submit(5100).expect("Failed to batch inventory menu draw");
+    textbatch.submit(16000).expect("Failed to batch inventory menu draw");
+}
@@ -0,0 +1,105 @@
+//The headless game core: world state, turn processing, map generation and saving.
+//Nothing in here touches the console, so it can be driven by tests or tools as well as the game itself.
+mod camera;
+mod map;
+mod object;
+mod entity;
+mod ecs;
+mod saves;
+mod spawn;
+mod world;
+mod branch;
+mod endgame;
+mod game;
+mod systems;
+mod actions;
+mod logs;
+mod inventory;
+mod dbinterface;
+mod aidef;
+mod replay;
+
+pub mod prelude {
+    pub use crate::camera::*;
+    pub use crate::map::*;
+    pub use crate::object::*;
+    pub use crate::entity::*;
+    pub use crate::ecs::*;
+    pub use crate::saves::*;
+    pub use crate::spawn::*;
+    pub use crate::world::*;
+    pub use crate::branch::*;
+    pub use crate::endgame::*;
+    pub use crate::game::*;
+    pub use crate::systems::*;
+    pub use crate::actions::*;
+    pub use crate::logs::*;
+    pub use crate::inventory::*;
+    pub use crate::dbinterface::*;
+    pub use crate::aidef::*;
+    pub use crate::replay::*;
+    pub use bracket_geometry::prelude::*;
+    pub use bracket_random::prelude::*;
+    pub use bracket_pathfinding::prelude::*;
+    pub use bracket_color::prelude::*;
+    pub use std::cmp::Reverse;
+
+    //Consts to define the drawable console space
+    pub const CONSOLE_W: i32 = 80;
+    pub const CONSOLE_H: i32 = 60;
+
+    //Constant point values used as directional indicators
+    pub const DL_LEFT: Point = Point    { x: -1, y:  0 };
+    pub const DL_RIGHT: Point = Point   { x:  1, y:  0 };
+    pub const DL_UP: Point = Point      { x:  0, y: -1 };
+    pub const DL_DOWN: Point = Point    { x:  0, y:  1 };
+
+    //Type alias for a vec of entity handles and initiative values
+    pub type InitList = Vec<(EntityId, u8)>;
+
+    pub trait InitListTrait {
+        fn add_object(&mut self, id: EntityId, init: u8);
+        fn sort(&mut self);
+    }
+    impl InitListTrait for InitList {
+        //Adds a new object to the list
+        fn add_object(&mut self, id: EntityId, init: u8) {
+            self.push((id, init));
+        }
+        //Sorts by descending initiative order
+        fn sort(&mut self) {
+            self.sort_by_key(|a| Reverse(a.1));
+        }
+    }
+
+    pub trait Neighbor {
+        fn get_neighbors(&self) -> Vec<Point>;
+        fn get_distant_neighbors(&self, mul_factor: i32) -> Vec<Point>;
+    }
+    impl Neighbor for Point {
+        fn get_neighbors(&self) -> Vec<Point> {
+            return vec![
+                *self + DL_UP,
+                *self + DL_DOWN,
+                *self + DL_LEFT,
+                *self + DL_RIGHT,
+                *self + DL_UP + DL_LEFT,
+                *self + DL_UP + DL_RIGHT,
+                *self + DL_DOWN + DL_LEFT,
+                *self + DL_DOWN + DL_RIGHT
+            ]
+        }
+        fn get_distant_neighbors(&self, mul_factor: i32) -> Vec<Point> {
+            return vec![
+                *self + (DL_UP * mul_factor),
+                *self + (DL_DOWN * mul_factor),
+                *self + (DL_LEFT * mul_factor),
+                *self + (DL_RIGHT * mul_factor),
+                *self + (DL_UP  * mul_factor) + (DL_LEFT * mul_factor),
+                *self + (DL_UP * mul_factor) + (DL_RIGHT * mul_factor),
+                *self + (DL_DOWN * mul_factor) + (DL_LEFT * mul_factor),
+                *self + (DL_DOWN * mul_factor) + (DL_RIGHT * mul_factor)
+            ]
+        }
+    }
+}
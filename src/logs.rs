@@ -5,25 +5,10 @@ const MAX_WIDTH: usize = (CONSOLE_W * 2 - UI_CUTOFF.x * 2 - 4) as usize;
 pub type LogBuffer = Vec<LogMessage>;
 
 pub trait LogBufferTrait {
-    fn format(&self) -> TextBuilder;
     fn update_logs(&mut self, message: LogMessage);
 }
 
 impl LogBufferTrait for LogBuffer {
-    fn format(&self) -> TextBuilder {
-        let mut builder = TextBuilder::empty();
-
-        for (n, message) in self.iter().rev().enumerate() {
-            for (i, part) in message.parts.iter().enumerate() {
-                let bg = message.colors[i].bg;
-                builder.bg(bg - RGBA::from_f32(0.01,0.01,0.01,0.0) * n as f32);
-                builder.fg(message.colors[i].fg);
-                builder.append(part);
-            }
-        }
-
-        return builder;
-    }
     fn update_logs(&mut self, message: LogMessage) {
         let mut group_list: Vec<(String, ColorPair)> = Vec::new();
 
@@ -1,33 +1,240 @@
 use crate::prelude::*;
-use bracket_lib::prelude::*;
 use std::cmp::{max, min};
 
 #[derive(Clone, Copy, PartialEq)]
 pub enum GenerationMode {
     RandomRooms,
-    Unimplemented
+    Bsp,
+    Caves,
+    DrunkardsWalk(CarverSettings),
+    Dla(CarverSettings)
 }
+impl GenerationMode {
+    //Picks the layout used for a given depth
+    pub fn for_depth(depth: i32) -> GenerationMode {
+        return match (depth - 1) % 5 {
+            0 => GenerationMode::RandomRooms,
+            1 => GenerationMode::Bsp,
+            2 => GenerationMode::Caves,
+            3 => GenerationMode::DrunkardsWalk(CarverSettings { floor_percent: 40, lifetime: 200 }),
+            _ => GenerationMode::Dla(CarverSettings { floor_percent: 35, lifetime: 400 })
+        }
+    }
+    //Layouts without a natural last room put the stairs as far from the start as can be walked
+    fn stairs_at_farthest(&self) -> bool {
+        return match self {
+            GenerationMode::DrunkardsWalk(_) | GenerationMode::Dla(_) => true,
+            _ => false
+        }
+    }
+}
+
+//Tuning for the walker-based carvers: they keep sending out walkers until this much of the map is
+//floor, and each walker gives up after this many steps
+#[derive(Clone, Copy, PartialEq)]
+pub struct CarverSettings {
+    pub floor_percent: i32,
+    pub lifetime: i32
+}
+
+//Partitions are never cut smaller than this, and ones smaller than the max may be left whole
+const BSP_MIN_LEAF: i32 = 10;
+const BSP_MAX_LEAF: i32 = 20;
+const BSP_MIN_ROOM: i32 = 3;
+
+//Cave tuning: how much of the map starts as wall, how many smoothing passes are run, and how the
+//finished cave gets chopped up into spawn regions
+const CAVE_WALL_CHANCE: i32 = 45;
+const CAVE_SMOOTH_PASSES: usize = 5;
+const FLOOR_REGION_SIZE: i32 = 12;
+const FLOOR_MIN_REGION: usize = 16;
+
+//Walker carvers stop after this many walkers per tile of map, even if they haven't hit their floor target
+const CARVER_WALKER_BUDGET: i32 = 4;
+
+//How many vaults a single floor can get, and how many spots each one tries before it's skipped
+const MAX_VAULTS_PER_FLOOR: usize = 2;
+const VAULT_PLACEMENT_ATTEMPTS: i32 = 40;
+
+//How often a doorway in a room layout is left standing open, or walled over as a secret door.
+//Secret doors only ever go where there's another way around.
+const DOOR_OPEN_CHANCE: i32 = 25;
+const SECRET_DOOR_CHANCE: i32 = 15;
+
+//How often a pair of neighbouring rooms gets a window between them, and the thickest wall one is cut through
+const WINDOW_CHANCE: i32 = 40;
+const WINDOW_MAX_THICKNESS: usize = 3;
+
+//Hazard pools: how many a floor gets on top of one per this many levels of depth, how many
+//tiles each kind grows to, and how deep the player has to be before the worse kinds turn up
+const HAZARD_POOLS_BASE: i32 = 2;
+const HAZARD_POOLS_DEPTH_STEP: i32 = 3;
+const HAZARD_POOLS_MAX: i32 = 6;
+const WATER_POOL_SIZE: (i32, i32) = (8, 30);
+const LAVA_POOL_SIZE: (i32, i32) = (4, 14);
+const CHASM_POOL_SIZE: (i32, i32) = (3, 8);
+const LAVA_MIN_DEPTH: i32 = 3;
+const CHASM_MIN_DEPTH: i32 = 2;
+
+//Random rooms stop trying to fit new rooms after this many misses
+const ROOM_PLACEMENT_ATTEMPTS: i32 = 500;
+//A layout that can't be made playable is thrown out and rebuilt this many times before falling back
+//to a single open room
+const MAX_GENERATION_ATTEMPTS: i32 = 10;
 
 pub struct MapGenerator {
     pub map: Map,
     pub rooms: Vec<Rect>,
+    pub regions: Vec<SpawnRegion>,
+    pub vaults: Vec<Rect>,
     pub depth: i32,
     pub max_rooms: usize,
     pub start_pos: Point,
     pub objects: Vec<Object>
 }
 impl MapGenerator {
-    //Public-facing map generation function
+    //Public-facing map generation function, building a floor to the depth's usual profile
+    pub fn for_depth(depth: i32, rng: &mut RandomNumberGenerator) -> MapGenerator {
+        return MapGenerator::from_profile(&LevelProfile::for_depth(depth), depth, rng)
+    }
+    //Same as above, but with the layout picked by hand
     pub fn generate(mode: GenerationMode, w: i32, h: i32, depth: i32, rng: &mut RandomNumberGenerator) -> MapGenerator {
-        match mode {
-            GenerationMode::RandomRooms => return MapGenerator::random_rooms_build(w, h, depth, rng),
-            _ => panic!("Unrecognized map generation mode!")
+        return MapGenerator::from_profile(&LevelProfile::for_depth(depth).with_layout(mode, w, h), depth, rng)
+    }
+    pub fn from_profile(profile: &LevelProfile, depth: i32, rng: &mut RandomNumberGenerator) -> MapGenerator {
+        let (mode, w, h) = (profile.mode, profile.width, profile.height);
+        for _ in 0..MAX_GENERATION_ATTEMPTS {
+            let gen = match mode {
+                GenerationMode::RandomRooms => MapGenerator::random_rooms_build(w, h, depth, profile.max_rooms, rng),
+                GenerationMode::Bsp => MapGenerator::bsp_build(w, h, depth, rng),
+                GenerationMode::Caves => MapGenerator::caves_build(w, h, depth, rng),
+                GenerationMode::DrunkardsWalk(settings) => MapGenerator::drunkards_walk_build(w, h, depth, settings, rng),
+                GenerationMode::Dla(settings) => MapGenerator::dla_build(w, h, depth, settings, rng)
+            };
+            if let Some(gen) = gen.finish(mode.stairs_at_farthest(), profile, rng) { return gen }
+        }
+
+        //Nothing usable came out, so hand back something that's at least winnable
+        let gen = MapGenerator::fallback_build(w, h, depth);
+        return gen.finish(false, profile, rng).expect("Fallback map could not be finished.")
+    }
+    //Checks that the stairs can be walked to from the start without stepping on any hazards
+    pub fn is_winnable(&self) -> bool {
+        let stairs = self.map.tiles.iter().position(|t| *t == TileClass::DownStair);
+        let start_idx = self.map.index(self.start_pos.x, self.start_pos.y);
+        if stairs.is_none() || self.map.tiles[start_idx].does_collide() { return false }
+
+        let reached = self.reachable_from(self.start_pos, true);
+        return reached[stairs.unwrap()]
+    }
+
+    //Puts the stairs down into a side branch somewhere past the start region that nothing else is using,
+    //or failing that, on any free floor that can be reached. Returns false if there was nowhere safe to put them.
+    pub fn place_branch_stairs(&mut self, rng: &mut RandomNumberGenerator) -> bool {
+        let taken: Vec<Point> = self.objects.iter().filter_map(|o| o.pos).collect();
+        let reached = self.reachable_from(self.start_pos, true);
+        let free = |p: &Point| {
+            let idx = self.map.index(p.x, p.y);
+            self.map.tiles[idx] == TileClass::Floor && reached[idx] && !taken.contains(p) && *p != self.start_pos
+        };
+        let mut spots: Vec<Point> = self.regions.iter().skip(1)
+            .flat_map(|r| r.points.iter().cloned())
+            .filter(|p| free(p))
+            .collect();
+        if spots.is_empty() {
+            spots = (0..self.map.tiles.len()).map(|idx| self.map.point_from_idx(idx)).filter(|p| free(p)).collect();
+        }
+        if spots.is_empty() { return false }
+
+        let pos = spots[rng.range(0, spots.len())];
+        let idx = self.map.index(pos.x, pos.y);
+        self.map.tiles[idx] = TileClass::BranchStair;
+        return true
+    }
+    //Closes off every way down, for the bottom floor of a branch
+    pub fn seal_bottom(&mut self) {
+        for tile in self.map.tiles.iter_mut() {
+            if *tile == TileClass::DownStair || *tile == TileClass::Chasm { *tile = TileClass::Floor }
+        }
+        for obj in self.objects.iter_mut() {
+            if obj.trap.map_or(false, |t| t.kind == TrapKind::Trapdoor) {
+                let (pos, floor) = (obj.pos, obj.floor);
+                *obj = make_trap(TrapKind::Dart);
+                obj.pos = pos;
+                obj.floor = floor;
+            }
+        }
+    }
+    //Sets up the last floor of the main dungeon: the goal item goes where the stairs down would have been,
+    //with the boss standing guard right next to it, and there's no way further down
+    pub fn place_final_encounter(&mut self, mut boss: Object, mut goal: Object) {
+        let lair = match self.map.tiles.iter().position(|t| *t == TileClass::DownStair) {
+            Some(idx) => self.map.point_from_idx(idx),
+            None => self.farthest_reachable_from(self.start_pos)
+        };
+        self.seal_bottom();
+
+        //Nothing else gets to sit in the lair
+        self.objects.retain(|o| o.pos.map_or(true, |p| p != lair && !lair.get_neighbors().contains(&p)));
+        let reached = self.reachable_from(self.start_pos, true);
+        let guard = lair.get_neighbors().into_iter()
+            .find(|p| self.map.try_index(p.x, p.y).map_or(false, |idx| self.map.tiles[idx] == TileClass::Floor && reached[idx]))
+            .unwrap_or(lair);
+
+        goal.pos = Some(lair);
+        boss.pos = Some(guard);
+        self.objects.push(goal);
+        self.objects.push(boss);
+    }
+
+    //Turns a carved layout into a playable floor: reconnects stray floor, adds vaults, stairs and spawns,
+    //then validates the result. Returns None if the layout has to be thrown out.
+    fn finish(mut self, stairs_at_farthest: bool, profile: &LevelProfile, rng: &mut RandomNumberGenerator) -> Option<MapGenerator> {
+        //Room layouts spawn things room by room
+        if self.regions.is_empty() {
+            self.regions = self.rooms.iter().map(|r| SpawnRegion::from_rect(r)).collect();
+        }
+        //There has to be somewhere to start, and somewhere else to spawn things
+        if self.regions.len() < 2 { return None }
+        if !self.reconnect_floor(self.regions[0].center, None) { return None }
+
+        let protected = self.protected_points(stairs_at_farthest);
+        self.place_vaults(&protected, rng);
+        self.place_hazards(&protected, rng);
+        self.place_secret_doors(rng);
+        self.prune_regions();
+        self.place_stairs_and_spawns(stairs_at_farthest, profile, rng);
+        self.place_light_sources(profile.light_sources, rng);
+
+        if !self.is_winnable() { return None }
+        return Some(self)
+    }
+
+    //Puts torches up against the walls, anywhere in the spawn regions that nothing else is using
+    fn place_light_sources(&mut self, count: i32, rng: &mut RandomNumberGenerator) {
+        let taken: Vec<Point> = self.objects.iter().filter_map(|o| o.pos).collect();
+        let map = &self.map;
+        let mut spots: Vec<Point> = self.regions.iter()
+            .flat_map(|r| r.points.iter().cloned())
+            .filter(|p| {
+                map.tiles[map.index(p.x, p.y)] == TileClass::Floor && !taken.contains(p) &&
+                [DL_UP, DL_DOWN, DL_LEFT, DL_RIGHT].iter()
+                    .any(|d| map.try_index(p.x + d.x, p.y + d.y).map_or(false, |idx| map.tiles[idx] == TileClass::Wall))
+            })
+            .collect();
+
+        for _ in 0..count {
+            if spots.is_empty() { return }
+            let pos = spots.remove(rng.range(0, spots.len()));
+            let mut torch = make_torch();
+            add_positional_info(&mut torch, pos, self.depth);
+            self.objects.push(torch);
         }
     }
 
     //Internal base constructor
     fn init(w: i32, h: i32, depth: i32, max_rooms: usize ) -> MapGenerator {
-        MapGenerator { map: Map::new(w, h), rooms: Vec::new(), depth, max_rooms, start_pos: Point::zero(), objects: Vec::new() }
+        MapGenerator { map: Map::new(w, h), rooms: Vec::new(), regions: Vec::new(), vaults: Vec::new(), depth, max_rooms, start_pos: Point::zero(), objects: Vec::new() }
     }
 
     //Fills the map with the specified tile class
@@ -50,37 +257,62 @@ impl MapGenerator {
             }
         }
     }
+    //Joins two points with an L-shaped pair of tunnels, bending one way or the other at random
+    fn make_l_tunnel(&mut self, from: Point, to: Point, rng: &mut RandomNumberGenerator) {
+        match rng.range(0, 2) {
+            0 => {
+                self.make_v_tunnel(from.y, to.y, from.x);
+                self.make_h_tunnel(from.x, to.x, to.y);
+            }
+            _ => {
+                self.make_h_tunnel(from.x, to.x, from.y);
+                self.make_v_tunnel(from.y, to.y, to.x);
+            }
+        }
+    }
+    //Carves a room out of the map and adds it to the room list
+    fn carve_room(&mut self, room: Rect) {
+        room.for_each(|p| {
+            if p.x > 0 && p.x < self.map.width && p.y > 0 && p.y < self.map.height {
+                let idx = self.map.index(p.x, p.y);
+                self.map.tiles[idx] = TileClass::Floor;
+            }
+        });
+        self.rooms.push(room);
+    }
 
-    /* RANDOMLY PLACED ROOMS */
-    //Builds a map using randomly placed rooms
-    fn random_rooms_build(w: i32, h: i32, depth: i32, rng: &mut RandomNumberGenerator) -> MapGenerator {
-        let mut gen = MapGenerator::init(w, h, depth, 20);
-        let mut block_list: Vec<Point> = Vec::new();
-
-        //Run all the map-making procedures
-        gen.fill(TileClass::Wall);
-        gen.make_randomly_placed_rooms(rng);
-        gen.add_corridors_sorted(rng);
+    //Shared by every mode once the layout is carved: the player starts in the first region, the stairs go
+    //in the last one (or as far away as possible), and enemies and items are spread through the rest as the profile says
+    fn place_stairs_and_spawns(&mut self, stairs_at_farthest: bool, profile: &LevelProfile, rng: &mut RandomNumberGenerator) {
+        let depth = self.depth;
+        //Anything a vault already put down stays where it is
+        let mut block_list: Vec<Point> = self.objects.iter().filter_map(|o| o.pos).collect();
 
-        //Set the start position
-        gen.start_pos = gen.rooms[0].center();
+        //Set the start position; below the first floor, that's where the stairs back up are
+        self.start_pos = self.regions[0].center;
+        if depth > 1 {
+            let start_idx = self.map.point2d_to_index(self.start_pos);
+            self.map.tiles[start_idx] = TileClass::UpStair;
+        }
 
-        //Place stairs as the last room's center
-        let last_center = gen.map.point2d_to_index(gen.rooms[gen.rooms.len()-1].center());
-        gen.map.tiles[last_center] = TileClass::DownStair;
+        //Place stairs as the last region's center, or the farthest walkable tile
+        let stairs = if stairs_at_farthest { self.farthest_reachable_from(self.start_pos) }
+                     else { self.regions[self.regions.len()-1].center };
+        let stairs_idx = self.map.point2d_to_index(stairs);
+        self.map.tiles[stairs_idx] = TileClass::DownStair;
 
         //Add vectors to track object positions for proximity calculation and room usage for even spawning
         let mut proximity_list: Vec<Point> = Vec::new();
         let mut room_nums: Vec<usize> = Vec::new();
         //Start spawning enemies
-        let enemy_spawns = get_enemy_spawn_table(depth, gen.rooms.len() as i32 - 1, rng);
-        for (i, _) in gen.rooms.iter().enumerate().skip(1) {
-            let mut obj = enemy_spawns[i - 1].clone();
-            if let Some(pos) = find_valid_spawn(&gen.rooms, &mut room_nums, &obj, &block_list, Some(&proximity_list), rng) {
+        let enemy_spawns = get_enemy_spawn_table(profile.spawn_table, profile.enemy_count(self.regions.len() - 1), rng);
+        for enemy in enemy_spawns.iter() {
+            let mut obj = enemy.clone();
+            if let Some(pos) = find_valid_spawn(&self.regions, &mut room_nums, &obj, &block_list, Some(&proximity_list), rng) {
                 add_positional_info(&mut obj, pos, depth);
                 block_list.push(pos);
                 proximity_list.push(pos);
-                gen.objects.push(obj)
+                self.objects.push(obj)
             }
         }
 
@@ -88,22 +320,250 @@ impl MapGenerator {
         proximity_list = Vec::new();
         room_nums = Vec::new();
         //Start spawning items
-        let item_spawns = get_item_spawns(depth, rng);
+        let item_spawns = get_item_spawns(profile.item_budget, rng);
         for item in item_spawns.iter() {
             let mut obj = item.clone();
-            if let Some(pos) = find_valid_spawn(&gen.rooms, &mut room_nums, &obj, &block_list, Some(&proximity_list), rng) {
+            if let Some(pos) = find_valid_spawn(&self.regions, &mut room_nums, &obj, &block_list, Some(&proximity_list), rng) {
                 add_positional_info(&mut obj, pos, depth);
                 block_list.push(pos);
                 proximity_list.push(pos);
-                gen.objects.push(obj);
+                self.objects.push(obj);
+            }
+        }
+
+        //Traps keep clear of everything else, the stairs included
+        block_list.push(stairs);
+        room_nums = Vec::new();
+        for trap in get_trap_spawns(depth, rng).into_iter() {
+            let mut obj = trap;
+            if let Some(pos) = find_valid_spawn(&self.regions, &mut room_nums, &obj, &block_list, None, rng) {
+                add_positional_info(&mut obj, pos, depth);
+                block_list.push(pos);
+                self.objects.push(obj);
+            }
+        }
+    }
+
+    //The start region and the stairs are left alone by everything stamped over the layout
+    fn protected_points(&self, stairs_at_farthest: bool) -> Vec<Point> {
+        let mut protected: Vec<Point> = self.regions[0].points.to_vec();
+        if !stairs_at_farthest { protected.push(self.regions[self.regions.len()-1].center) }
+        return protected
+    }
+    //Drops spawn points that aren't plain floor any more, and regions left with none.
+    //The start and stairs regions are always kept.
+    fn prune_regions(&mut self) {
+        let map = &self.map;
+        let last = self.regions.len() - 1;
+        for region in self.regions.iter_mut() {
+            region.points.retain(|p| map.tiles[map.index(p.x, p.y)] == TileClass::Floor);
+            let old = region.center;
+            if let Some(c) = region.points.iter().min_by_key(|p| (p.x - old.x).abs() + (p.y - old.y).abs()) {
+                region.center = *c;
+            }
+        }
+        let mut i = 0;
+        self.regions.retain(|r| {
+            let keep = i == 0 || i == last || !r.points.is_empty();
+            i += 1;
+            keep
+        });
+    }
+
+    /* PREFAB VAULTS */
+    //Rolls each vault allowed at this depth against its chance, and stamps the ones that come up
+    //somewhere they fit
+    fn place_vaults(&mut self, protected: &Vec<Point>, rng: &mut RandomNumberGenerator) {
+        let conn = open_connection();
+        let templates = import_vault_templates(&conn, self.depth).unwrap_or(vec![]);
+
+        for vault in templates.iter() {
+            if self.vaults.len() >= MAX_VAULTS_PER_FLOOR { break }
+            if rng.range(0, 100) >= vault.chance { continue }
+
+            for _ in 0..VAULT_PLACEMENT_ATTEMPTS {
+                if self.try_stamp_vault(vault, protected, &conn, rng) { break }
+            }
+        }
+        conn.close().expect("Connection to SQLite DB failed to close.");
+    }
+    //Stamps a vault at a random spot, then tunnels out from it until everything is connected again.
+    //If that can't be done the map is put back the way it was.
+    fn try_stamp_vault(&mut self, vault: &VaultTemplate, protected: &Vec<Point>, conn: &rusqlite::Connection, rng: &mut RandomNumberGenerator) -> bool {
+        let (w, h) = (vault.width(), vault.height());
+        if w < 1 || h < 1 || w > self.map.width - 2 || h > self.map.height - 2 { return false }
+
+        let area = Rect::with_size(rng.range(1, self.map.width - w), rng.range(1, self.map.height - h), w, h);
+        if protected.iter().any(|p| area.point_in_rect(*p)) { return false }
+        if self.vaults.iter().any(|v| v.intersect(&area)) { return false }
+
+        let old_tiles = self.map.tiles.to_vec();
+        for y in 0..h {
+            for x in 0..w {
+                if let Some(tile) = vault.tile_at(x, y) {
+                    let idx = self.map.index(area.x1 + x, area.y1 + y);
+                    self.map.tiles[idx] = tile;
+                }
+            }
+        }
+        if !self.reconnect_floor(self.regions[0].center, Some(&area)) {
+            self.map.tiles = old_tiles;
+            return false
+        }
+
+        for y in 0..h {
+            for x in 0..w {
+                let spawned = match vault.spawn_at(x, y) {
+                    Some(VaultSpawn::Enemy(id)) => import_enemies_to_objects(conn, String::from("V_EnemiesFull"), Some(format!("id = {}", id))),
+                    Some(VaultSpawn::Item(id)) => import_items_to_objects(conn, String::from("V_ItemsFull"), Some(format!("id = {}", id))),
+                    None => None
+                };
+                if let Some(mut obj) = spawned.and_then(|mut v| v.pop()) {
+                    add_positional_info(&mut obj, Point::new(area.x1 + x, area.y1 + y), self.depth);
+                    self.objects.push(obj);
+                }
+            }
+        }
+        self.vaults.push(area);
+        return true
+    }
+    //Digs tunnels until every floor tile can be walked to from the start. Tunnels never cut through the
+    //kept-out area, though paths may cross its floor. Returns false if some floor can't be reached at all.
+    fn reconnect_floor(&mut self, start: Point, keep_out: Option<&Rect>) -> bool {
+        loop {
+            let reached = self.reachable_from(start, false);
+            let cut_off = (0..self.map.tiles.len()).find(|i| !reached[*i] && can_walk_through(&self.map.tiles[*i]));
+            let cut_off = match cut_off {
+                Some(idx) => idx,
+                None => return true
+            };
+
+            //Breadth-first search out from the cut off tile to the nearest reached one
+            let mut came_from: Vec<Option<usize>> = vec![None; self.map.tiles.len()];
+            let mut open = std::collections::VecDeque::new();
+            let mut found: Option<usize> = None;
+            came_from[cut_off] = Some(cut_off);
+            open.push_back(cut_off);
+            while let Some(idx) = open.pop_front() {
+                if reached[idx] { found = Some(idx); break }
+                let pos = self.map.point_from_idx(idx);
+                for n in vec![pos + DL_LEFT, pos + DL_RIGHT, pos + DL_UP, pos + DL_DOWN].into_iter() {
+                    if n.x < 1 || n.x > self.map.width - 2 || n.y < 1 || n.y > self.map.height - 2 { continue }
+                    let nidx = self.map.index(n.x, n.y);
+                    let passable = !keep_out.map_or(false, |r| r.point_in_rect(n)) || can_walk_through(&self.map.tiles[nidx]);
+                    if passable && came_from[nidx].is_none() {
+                        came_from[nidx] = Some(idx);
+                        open.push_back(nidx);
+                    }
+                }
+            }
+
+            let mut idx = match found {
+                Some(idx) => idx,
+                None => return false
+            };
+            while idx != cut_off {
+                if !can_walk_through(&self.map.tiles[idx]) { self.map.tiles[idx] = TileClass::Floor }
+                idx = came_from[idx].unwrap();
+            }
+        }
+    }
+    //Flood fills the walkable tiles from a point, going through doors and optionally around hazards
+    fn reachable_from(&self, start: Point, avoid_hazards: bool) -> Vec<bool> {
+        let mut reached = vec![false; self.map.tiles.len()];
+        let mut open = vec![start];
+        reached[self.map.index(start.x, start.y)] = true;
+
+        while let Some(p) = open.pop() {
+            for n in p.get_neighbors() {
+                if let Some(idx) = self.map.try_index(n.x, n.y) {
+                    let tile = self.map.tiles[idx];
+                    if !reached[idx] && can_walk_through(&tile) && !(avoid_hazards && tile.is_hazard()) {
+                        reached[idx] = true;
+                        open.push(n);
+                    }
+                }
+            }
+        }
+        return reached
+    }
+
+    /* HAZARDS */
+    //Grows pools of water, lava and chasm out of the floor. A pool that would leave any floor only
+    //reachable by wading through something nasty is taken back out.
+    fn place_hazards(&mut self, protected: &Vec<Point>, rng: &mut RandomNumberGenerator) {
+        let start = self.regions[0].center;
+        let pools = min(HAZARD_POOLS_MAX, rng.range(0, HAZARD_POOLS_BASE + 1) + self.depth / HAZARD_POOLS_DEPTH_STEP);
+
+        for _ in 0..pools {
+            let (kind, size) = match rng.range(0, 4) {
+                0 if self.depth >= LAVA_MIN_DEPTH => (TileClass::Lava, LAVA_POOL_SIZE),
+                1 if self.depth >= CHASM_MIN_DEPTH => (TileClass::Chasm, CHASM_POOL_SIZE),
+                _ => (TileClass::DeepWater, WATER_POOL_SIZE)
+            };
+            let region = &self.regions[rng.range(1, self.regions.len())];
+            if region.points.is_empty() { continue }
+            let seed = region.points[rng.range(0, region.points.len())];
+
+            let pool = self.grow_pool(seed, rng.range(size.0, size.1 + 1), protected, rng);
+            if pool.is_empty() { continue }
+
+            let safe_before = self.reachable_from(start, true);
+            let old_tiles = self.map.tiles.to_vec();
+            for p in pool.iter() {
+                //Water only gets deep where it's surrounded by more water
+                let tile = if kind == TileClass::DeepWater && !p.get_neighbors().iter().all(|n| pool.contains(n)) {
+                    TileClass::ShallowWater
+                } else {
+                    kind
+                };
+                let idx = self.map.index(p.x, p.y);
+                self.map.tiles[idx] = tile;
             }
+
+            let safe_after = self.reachable_from(start, true);
+            let cut_off = (0..safe_before.len()).any(|i| safe_before[i] && !safe_after[i] && !self.map.tiles[i].is_hazard());
+            if cut_off { self.map.tiles = old_tiles }
+        }
+    }
+    //Spreads a blob of floor tiles out from a point, staying clear of vaults and protected tiles
+    fn grow_pool(&self, seed: Point, size: i32, protected: &Vec<Point>, rng: &mut RandomNumberGenerator) -> Vec<Point> {
+        let usable = |p: Point| {
+            self.map.in_bounds(p.x, p.y)
+                && self.map.tiles[self.map.index(p.x, p.y)] == TileClass::Floor
+                && !protected.contains(&p)
+                && !self.vaults.iter().any(|v| v.point_in_rect(p))
+        };
+        if !usable(seed) { return Vec::new() }
+
+        let mut pool = vec![seed];
+        for _ in 0..size * 4 {
+            if pool.len() as i32 >= size { break }
+            let from = pool[rng.range(0, pool.len())];
+            let next = from + match rng.range(0, 4) { 0 => DL_LEFT, 1 => DL_RIGHT, 2 => DL_UP, _ => DL_DOWN };
+            if usable(next) && !pool.contains(&next) { pool.push(next) }
         }
+        return pool
+    }
+
+    /* RANDOMLY PLACED ROOMS */
+    //Builds a map using randomly placed rooms
+    fn random_rooms_build(w: i32, h: i32, depth: i32, max_rooms: usize, rng: &mut RandomNumberGenerator) -> MapGenerator {
+        let mut gen = MapGenerator::init(w, h, depth, max_rooms);
+
+        //Run all the map-making procedures
+        gen.fill(TileClass::Wall);
+        gen.make_randomly_placed_rooms(rng);
+        gen.add_corridors_sorted(rng);
+        gen.place_doors(rng);
+        gen.place_windows(rng);
 
         return gen;
     }
-    //Creates some rects of flooring to create rooms
+    //Creates some rects of flooring to create rooms, giving up once too many in a row don't fit
     fn make_randomly_placed_rooms(&mut self, rng: &mut RandomNumberGenerator) {
-        while self.rooms.len() < self.max_rooms {
+        let mut attempts = ROOM_PLACEMENT_ATTEMPTS;
+        while self.rooms.len() < self.max_rooms && attempts > 0 {
             let room = Rect::with_size(
                 rng.range(1, self.map.width - 10),
                 rng.range(1, self.map.height - 10),
@@ -119,14 +579,11 @@ impl MapGenerator {
             }
 
             if !overlap {
-                room.for_each(|p| {
-                    if p.x > 0 && p.x < self.map.width && p.y > 0 && p.y < self.map.height {
-                        let idx = self.map.index(p.x, p.y);
-                        self.map.tiles[idx] = TileClass::Floor;
-                    }
-                });
-
-                self.rooms.push(room);
+                self.carve_room(room);
+                attempts = ROOM_PLACEMENT_ATTEMPTS;
+            }
+            else {
+                attempts -= 1;
             }
         }
     }
@@ -139,21 +596,401 @@ impl MapGenerator {
             let prev_center = rooms[i - 1].center();
             let this_center = room.center();
 
-            match rng.range(0, 2) {
-                0 => {
-                    self.make_v_tunnel(prev_center.y, this_center.y, prev_center.x);
-                    self.make_h_tunnel(prev_center.x, this_center.x, this_center.y);
+            self.make_l_tunnel(prev_center, this_center, rng);
+        }
+    }
+
+    //Puts a door wherever a one-tile-wide corridor breaks through the edge of a room
+    fn place_doors(&mut self, rng: &mut RandomNumberGenerator) {
+        let rooms = self.rooms.clone();
+        for room in rooms.iter() {
+            let mut doorways: Vec<Point> = Vec::new();
+            for x in room.x1..room.x2 {
+                for y in vec![room.y1 - 1, room.y2].into_iter() {
+                    if self.is_doorway(Point::new(x, y), DL_LEFT, &rooms) { doorways.push(Point::new(x, y)) }
+                }
+            }
+            for y in room.y1..room.y2 {
+                for x in vec![room.x1 - 1, room.x2].into_iter() {
+                    if self.is_doorway(Point::new(x, y), DL_UP, &rooms) { doorways.push(Point::new(x, y)) }
+                }
+            }
+
+            for pos in doorways.into_iter() {
+                let idx = self.map.index(pos.x, pos.y);
+                self.map.tiles[idx] = if rng.range(0, 100) < DOOR_OPEN_CHANCE { TileClass::DoorOpen } else { TileClass::DoorClosed };
+            }
+        }
+    }
+    //Hides some of the doors as secret ones, as long as nothing gets cut off from the start without them
+    fn place_secret_doors(&mut self, rng: &mut RandomNumberGenerator) {
+        let start = self.regions[0].center;
+        let reached = self.reachable_from(start, true).iter().filter(|r| **r).count();
+
+        let doors: Vec<usize> = (0..self.map.tiles.len()).filter(|idx| self.map.tiles[*idx].is_door()).collect();
+        let mut hidden = 0;
+        for idx in doors.into_iter() {
+            if rng.range(0, 100) >= SECRET_DOOR_CHANCE { continue }
+
+            let old_tile = self.map.tiles[idx];
+            self.map.tiles[idx] = TileClass::SecretDoor;
+            if self.reachable_from(start, true).iter().filter(|r| **r).count() == reached - hidden - 1 { hidden += 1 }
+            else { self.map.tiles[idx] = old_tile }
+        }
+    }
+    //A doorway is corridor floor outside of every room, with wall on both sides along the room's edge
+    fn is_doorway(&self, pos: Point, along: Point, rooms: &Vec<Rect>) -> bool {
+        let (a, b) = (pos + along, pos - along);
+        if !self.map.in_bounds(a.x, a.y) || !self.map.in_bounds(b.x, b.y) { return false }
+        if self.map.tiles[self.map.index(pos.x, pos.y)] != TileClass::Floor { return false }
+        if rooms.iter().any(|r| r.point_in_rect(pos)) { return false }
+
+        return self.map.tiles[self.map.index(a.x, a.y)] == TileClass::Wall
+            && self.map.tiles[self.map.index(b.x, b.y)] == TileClass::Wall
+    }
+
+    //Glazes over a short run of wall between a room and whatever room lies just right of or below it,
+    //so the far side can be seen before it can be reached
+    fn place_windows(&mut self, rng: &mut RandomNumberGenerator) {
+        let rooms = self.rooms.clone();
+        for (i, room) in rooms.iter().enumerate() {
+            let right: Vec<Point> = (room.y1..room.y2).map(|y| Point::new(room.x2 - 1, y)).collect();
+            let below: Vec<Point> = (room.x1..room.x2).map(|x| Point::new(x, room.y2 - 1)).collect();
+
+            for (edge, dir) in vec![(right, DL_RIGHT), (below, DL_DOWN)].into_iter() {
+                let spots: Vec<Vec<Point>> = edge.iter()
+                    .filter_map(|start| self.window_from(*start, dir, i, &rooms))
+                    .collect();
+                if spots.is_empty() || rng.range(0, 100) >= WINDOW_CHANCE { continue }
+
+                for p in spots[rng.range(0, spots.len())].iter() {
+                    let idx = self.map.index(p.x, p.y);
+                    self.map.tiles[idx] = TileClass::Glass;
                 }
-                _ => {
-                    self.make_h_tunnel(prev_center.x, this_center.x, prev_center.y);
-                    self.make_v_tunnel(prev_center.y, this_center.y, this_center.x);
+            }
+        }
+    }
+    //Walks out from a room's edge through wall, returning the wall tiles if another room is on the other side
+    fn window_from(&self, start: Point, dir: Point, room: usize, rooms: &Vec<Rect>) -> Option<Vec<Point>> {
+        let mut wall: Vec<Point> = Vec::new();
+        let mut pos = start + dir;
+        while self.map.in_bounds(pos.x, pos.y) && self.map.tiles[self.map.index(pos.x, pos.y)] == TileClass::Wall {
+            if wall.len() >= WINDOW_MAX_THICKNESS { return None }
+            wall.push(pos);
+            pos = pos + dir;
+        }
+
+        let other_room = rooms.iter().enumerate().any(|(j, r)| j != room && r.point_in_rect(pos));
+        return if !wall.is_empty() && other_room { Some(wall) } else { None }
+    }
+
+    /* FALLBACK */
+    //One open room split into two halves, used when nothing else gives a playable floor
+    fn fallback_build(w: i32, h: i32, depth: i32) -> MapGenerator {
+        let mut gen = MapGenerator::init(w, h, depth, 2);
+        gen.fill(TileClass::Wall);
+
+        let half = (w - 2) / 2;
+        gen.carve_room(Rect::with_size(1, 1, half - 1, h - 3));
+        gen.carve_room(Rect::with_size(half + 1, 1, w - half - 3, h - 3));
+        gen.make_h_tunnel(half - 1, half + 1, h / 2);
+
+        return gen
+    }
+
+    /* BINARY SPACE PARTITIONING */
+    //Builds a map by recursively cutting it into partitions, with one room per leaf
+    fn bsp_build(w: i32, h: i32, depth: i32, rng: &mut RandomNumberGenerator) -> MapGenerator {
+        let mut gen = MapGenerator::init(w, h, depth, 0);
+
+        gen.fill(TileClass::Wall);
+        gen.bsp_partition(Rect::with_size(0, 0, w, h), rng);
+        gen.max_rooms = gen.rooms.len();
+        gen.place_doors(rng);
+        gen.place_windows(rng);
+
+        return gen;
+    }
+    //Splits an area in two and recurses into each half, or carves a room if it's small enough.
+    //Once both halves are done, the closest pair of rooms across the cut gets a corridor, so every
+    //pair of sibling partitions ends up joined. Returns the indices of the rooms made inside the area.
+    fn bsp_partition(&mut self, area: Rect, rng: &mut RandomNumberGenerator) -> Vec<usize> {
+        let can_split_x = area.width() >= BSP_MIN_LEAF * 2;
+        let can_split_y = area.height() >= BSP_MIN_LEAF * 2;
+        let small_enough = area.width() <= BSP_MAX_LEAF && area.height() <= BSP_MAX_LEAF;
+
+        if (!can_split_x && !can_split_y) || (small_enough && rng.range(0, 3) == 0) {
+            self.carve_room(bsp_room_in(&area, rng));
+            return vec![self.rooms.len() - 1]
+        }
+
+        //Cut across the longer side so partitions stay roughly square
+        let split_x = if can_split_x && can_split_y { area.width() >= area.height() } else { can_split_x };
+        let (first, second) = if split_x {
+            let cut = rng.range(BSP_MIN_LEAF, area.width() - BSP_MIN_LEAF + 1);
+            (Rect::with_size(area.x1, area.y1, cut, area.height()),
+             Rect::with_size(area.x1 + cut, area.y1, area.width() - cut, area.height()))
+        } else {
+            let cut = rng.range(BSP_MIN_LEAF, area.height() - BSP_MIN_LEAF + 1);
+            (Rect::with_size(area.x1, area.y1, area.width(), cut),
+             Rect::with_size(area.x1, area.y1 + cut, area.width(), area.height() - cut))
+        };
+
+        let mut rooms = self.bsp_partition(first, rng);
+        let other_rooms = self.bsp_partition(second, rng);
+
+        let mut closest: Option<(Point, Point, f32)> = None;
+        for a in rooms.iter() {
+            for b in other_rooms.iter() {
+                let (ca, cb) = (self.rooms[*a].center(), self.rooms[*b].center());
+                let dist = DistanceAlg::Pythagoras.distance2d(ca, cb);
+                if closest.map_or(true, |c| dist < c.2) { closest = Some((ca, cb, dist)) }
+            }
+        }
+        if let Some((from, to, _)) = closest {
+            self.make_l_tunnel(from, to, rng);
+        }
+
+        rooms.extend(other_rooms);
+        return rooms
+    }
+
+    /* CELLULAR AUTOMATA CAVES */
+    //Builds a cave by smoothing random noise, then throwing away every pocket that isn't part of the main cave
+    fn caves_build(w: i32, h: i32, depth: i32, rng: &mut RandomNumberGenerator) -> MapGenerator {
+        let mut gen = MapGenerator::init(w, h, depth, 0);
+
+        gen.fill(TileClass::Wall);
+        for y in 1..h - 1 {
+            for x in 1..w - 1 {
+                if rng.range(0, 100) >= CAVE_WALL_CHANCE {
+                    let idx = gen.map.index(x, y);
+                    gen.map.tiles[idx] = TileClass::Floor;
                 }
             }
         }
+        for pass in 0..CAVE_SMOOTH_PASSES {
+            //Early passes also fill in wide open spaces, which breaks up big empty caverns
+            gen.smooth_caves(pass < 3);
+        }
+        gen.prune_disconnected_caves();
+        gen.split_floor_into_regions(None);
+
+        return gen;
     }
+    //One cellular automata step: a tile turns to wall when most of its neighbours are walls, and walls stay put at half
+    fn smooth_caves(&mut self, fill_open: bool) {
+        let mut new_tiles = self.map.tiles.to_vec();
+        for y in 1..self.map.height - 1 {
+            for x in 1..self.map.width - 1 {
+                let pos = Point::new(x, y);
+                let walls = pos.get_neighbors().iter().filter(|p| self.is_wall_or_edge(**p)).count();
+                let far_walls = walls + pos.get_distant_neighbors(2).iter().filter(|p| self.is_wall_or_edge(**p)).count();
+                let is_wall = self.map.tiles[self.map.index(x, y)] == TileClass::Wall;
+                new_tiles[self.map.index(x, y)] =
+                    if walls >= 5 || (is_wall && walls >= 4) || (fill_open && far_walls <= 2) { TileClass::Wall } else { TileClass::Floor };
+            }
+        }
+        self.map.tiles = new_tiles;
+    }
+    fn is_wall_or_edge(&self, pos: Point) -> bool {
+        return match self.map.try_index(pos.x, pos.y) {
+            Some(idx) => self.map.tiles[idx] == TileClass::Wall,
+            None => true
+        }
+    }
+    //Flood fills every pocket of floor, keeps the biggest one and walls off the rest
+    fn prune_disconnected_caves(&mut self) {
+        let mut pocket_of: Vec<Option<usize>> = vec![None; self.map.tiles.len()];
+        let mut sizes: Vec<usize> = Vec::new();
+
+        for start in 0..self.map.tiles.len() {
+            if self.map.tiles[start] == TileClass::Wall || pocket_of[start].is_some() { continue }
+
+            let pocket = sizes.len();
+            let mut size = 0;
+            let mut open = vec![start];
+            pocket_of[start] = Some(pocket);
+            while let Some(idx) = open.pop() {
+                size += 1;
+                for n in self.map.point_from_idx(idx).get_neighbors() {
+                    if let Some(nidx) = self.map.try_index(n.x, n.y) {
+                        if self.map.tiles[nidx] != TileClass::Wall && pocket_of[nidx].is_none() {
+                            pocket_of[nidx] = Some(pocket);
+                            open.push(nidx);
+                        }
+                    }
+                }
+            }
+            sizes.push(size);
+        }
+
+        let biggest = (0..sizes.len()).max_by_key(|i| sizes[*i]);
+        for (idx, pocket) in pocket_of.iter().enumerate() {
+            if *pocket != biggest { self.map.tiles[idx] = TileClass::Wall }
+        }
+        //Noise with no floor left at all still needs somewhere to stand
+        if biggest.is_none() {
+            let center = self.map.index(self.map.width / 2, self.map.height / 2);
+            self.map.tiles[center] = TileClass::Floor;
+        }
+    }
+    //Chops a roomless map into a grid of chunks and uses the floor in each as a spawn region. The start
+    //region is the one holding the given start point (or the first one found), and the rest are
+    //ordered by how far away they are from it.
+    fn split_floor_into_regions(&mut self, start: Option<Point>) {
+        let mut regions: Vec<SpawnRegion> = Vec::new();
+        for cy in (0..self.map.height).step_by(FLOOR_REGION_SIZE as usize) {
+            for cx in (0..self.map.width).step_by(FLOOR_REGION_SIZE as usize) {
+                let mut points: Vec<Point> = Vec::new();
+                Rect::with_size(cx, cy, FLOOR_REGION_SIZE, FLOOR_REGION_SIZE).for_each(|p| {
+                    if self.map.in_bounds(p.x, p.y) && self.map.tiles[self.map.index(p.x, p.y)] != TileClass::Wall {
+                        points.push(p);
+                    }
+                });
+                let holds_start = start.map_or(false, |s| points.contains(&s));
+                if points.len() >= FLOOR_MIN_REGION || holds_start {
+                    let mut region = SpawnRegion::from_points(points);
+                    if holds_start {
+                        region.center = start.unwrap();
+                        regions.insert(0, region);
+                    }
+                    else { regions.push(region) }
+                }
+            }
+        }
+        //A tiny map still needs one region to start in
+        if regions.is_empty() {
+            let mut points: Vec<Point> = Vec::new();
+            for (idx, tile) in self.map.tiles.iter().enumerate() {
+                if *tile != TileClass::Wall { points.push(self.map.point_from_idx(idx)) }
+            }
+            regions.push(SpawnRegion::from_points(points));
+        }
+
+        let start = regions[0].center;
+        regions[1..].sort_by(|a, b| {
+            let da = DistanceAlg::PythagorasSquared.distance2d(start, a.center);
+            let db = DistanceAlg::PythagorasSquared.distance2d(start, b.center);
+            da.partial_cmp(&db).unwrap()
+        });
+        self.regions = regions;
+    }
+
+    /* DRUNKARD'S WALK */
+    //Sends out walkers that stumble about at random, carving floor wherever they go. The first starts in
+    //the middle of the map and the rest start from random spots that are already carved out.
+    fn drunkards_walk_build(w: i32, h: i32, depth: i32, settings: CarverSettings, rng: &mut RandomNumberGenerator) -> MapGenerator {
+        let mut gen = MapGenerator::init(w, h, depth, 0);
+        let start = Point::new(w / 2, h / 2);
+
+        gen.fill(TileClass::Wall);
+        gen.set_floor(start);
+        let mut floor: Vec<Point> = vec![start];
+        let target = gen.floor_target(settings);
+
+        let mut walkers = w * h * CARVER_WALKER_BUDGET;
+        while (floor.len() as i32) < target && walkers > 0 {
+            walkers -= 1;
+            let mut pos = if floor.len() == 1 { start } else { floor[rng.range(0, floor.len())] };
+
+            for _ in 0..settings.lifetime {
+                pos = gen.step_walker(pos, rng);
+                if gen.set_floor(pos) { floor.push(pos) }
+            }
+        }
+        gen.split_floor_into_regions(Some(start));
+
+        return gen;
+    }
+
+    /* DIFFUSION-LIMITED AGGREGATION */
+    //Grows the map inwards-out: walkers start somewhere random in the rock and wander until they bump
+    //into the carved area, then carve the last tile they stood on. Walkers that run out of steps first
+    //are lost, so everything carved is always attached to the middle.
+    fn dla_build(w: i32, h: i32, depth: i32, settings: CarverSettings, rng: &mut RandomNumberGenerator) -> MapGenerator {
+        let mut gen = MapGenerator::init(w, h, depth, 0);
+        let start = Point::new(w / 2, h / 2);
+
+        gen.fill(TileClass::Wall);
+        let mut floor_count = 0;
+        for p in vec![start, start + DL_LEFT, start + DL_RIGHT, start + DL_UP, start + DL_DOWN].into_iter() {
+            if gen.set_floor(p) { floor_count += 1 }
+        }
+        let target = gen.floor_target(settings);
+
+        let mut walkers = w * h * CARVER_WALKER_BUDGET;
+        while floor_count < target && walkers > 0 {
+            walkers -= 1;
+            let mut pos = Point::new(rng.range(1, w - 1), rng.range(1, h - 1));
+            if !gen.is_wall_or_edge(pos) { continue }
+
+            for _ in 0..settings.lifetime {
+                let next = gen.step_walker(pos, rng);
+                if !gen.is_wall_or_edge(next) {
+                    if gen.set_floor(pos) { floor_count += 1 }
+                    break
+                }
+                pos = next;
+            }
+        }
+        gen.split_floor_into_regions(Some(start));
+
+        return gen;
+    }
+
+    //How many floor tiles a carver is aiming for
+    fn floor_target(&self, settings: CarverSettings) -> i32 {
+        return self.map.width * self.map.height * settings.floor_percent.max(1).min(90) / 100
+    }
+    //Moves a walker one tile in a random direction, without letting it onto the map's outer edge
+    fn step_walker(&self, pos: Point, rng: &mut RandomNumberGenerator) -> Point {
+        let delta = match rng.range(0, 4) {
+            0 => DL_LEFT,
+            1 => DL_RIGHT,
+            2 => DL_UP,
+            _ => DL_DOWN
+        };
+        let next = pos + delta;
+        return if next.x < 1 || next.x > self.map.width - 2 || next.y < 1 || next.y > self.map.height - 2 { pos } else { next }
+    }
+    //Carves a single tile, returning true if it wasn't already floor
+    fn set_floor(&mut self, pos: Point) -> bool {
+        let idx = self.map.index(pos.x, pos.y);
+        let was_wall = self.map.tiles[idx] == TileClass::Wall;
+        self.map.tiles[idx] = TileClass::Floor;
+        return was_wall
+    }
+    //Finds the plain floor tile with the longest path from a point
+    fn farthest_reachable_from(&self, start: Point) -> Point {
+        let starts = vec![self.map.index(start.x, start.y)];
+        let dijkstra = DijkstraMap::new(self.map.width, self.map.height, &starts, &self.map, (self.map.width * self.map.height) as f32);
+
+        let mut farthest = (start, 0.0);
+        for (idx, dist) in dijkstra.map.iter().enumerate() {
+            if *dist < f32::MAX && *dist > farthest.1 && self.map.tiles[idx] == TileClass::Floor {
+                farthest = (self.map.point_from_idx(idx), *dist);
+            }
+        }
+        return farthest.0
+    }
+}
+
+//Whether a tile can be walked onto during generation, counting doors as open
+fn can_walk_through(tile: &TileClass) -> bool {
+    return !tile.does_collide() || tile.is_door()
+}
+
+//Picks a room inside a BSP leaf, leaving at least a tile of wall on every side
+fn bsp_room_in(area: &Rect, rng: &mut RandomNumberGenerator) -> Rect {
+    let w = rng.range(BSP_MIN_ROOM, max(BSP_MIN_ROOM, area.width() - 2) + 1);
+    let h = rng.range(BSP_MIN_ROOM, max(BSP_MIN_ROOM, area.height() - 2) + 1);
+    let x = area.x1 + rng.range(1, max(1, area.width() - w - 1) + 1);
+    let y = area.y1 + rng.range(1, max(1, area.height() - h - 1) + 1);
+    return Rect::with_size(x, y, w, h)
 }
 
-fn find_valid_spawn(rooms: &Vec<Rect>, room_nums: &mut Vec<usize>, obj: &Object, block_list: &Vec<Point>, proximity_list: Option<&Vec<Point>>, rng: &mut RandomNumberGenerator) -> Option<Point> {
+fn find_valid_spawn(regions: &Vec<SpawnRegion>, room_nums: &mut Vec<usize>, obj: &Object, block_list: &Vec<Point>, proximity_list: Option<&Vec<Point>>, rng: &mut RandomNumberGenerator) -> Option<Point> {
     let mut iter_cnt: u16 = 1024;
 
     let proximity_graph = {
@@ -172,7 +1009,7 @@ fn find_valid_spawn(rooms: &Vec<Rect>, room_nums: &mut Vec<usize>, obj: &Object,
     };
 
     loop {
-        if room_nums.len() < 1 { *room_nums = (1 as usize..rooms.len()).map(|x| x).collect::<Vec<usize>>(); }
+        if room_nums.len() < 1 { *room_nums = (1 as usize..regions.len()).map(|x| x).collect::<Vec<usize>>(); }
 
         let rand_num = rng.range(0, room_nums.len());
         let room_num = room_nums.remove(rand_num);
@@ -182,7 +1019,7 @@ fn find_valid_spawn(rooms: &Vec<Rect>, room_nums: &mut Vec<usize>, obj: &Object,
             graph.append(&mut proximity_graph.to_vec());
             graph
         };
-        let pos = try_find_spawnable_position(&rooms[room_num],
+        let pos = try_find_spawnable_position(&regions[room_num],
                                               &block_graph, obj.block_tile, rng);
 
         if pos.is_some() { return pos }
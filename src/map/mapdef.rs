@@ -1,4 +1,3 @@
-use bracket_lib::prelude::*;
 use serde::{Deserialize, Serialize};
 use crate::prelude::*;
 
@@ -6,13 +5,40 @@ use crate::prelude::*;
 #[repr(u16)]
 pub enum TileClass {
     Wall = 1,
+    SecretDoor = 256,
+    DoorClosed = 512,
     Glass = 1024,
     Floor = 2048,
+    ShallowWater = 2304,
+    DeepWater = 2560,
+    Lava = 2816,
+    DoorOpen = 3072,
+    Chasm = 3584,
+    UpStair = 3840,
+    BranchStair = 3968,
     DownStair = 4096,
 }
 impl TileClass {
     pub fn does_collide(&self) -> bool { *self < TileClass::Floor }
     pub fn does_blos(&self) -> bool { *self < TileClass::Glass }
+    pub fn is_door(&self) -> bool { *self == TileClass::DoorClosed || *self == TileClass::DoorOpen }
+    //Secret doors pass for wall until someone finds them
+    pub fn looks_like(&self) -> TileClass {
+        return if *self == TileClass::SecretDoor { TileClass::Wall } else { *self }
+    }
+    //Hazards can be walked on, but something bad happens to whatever stands on them
+    pub fn is_hazard(&self) -> bool {
+        *self == TileClass::DeepWater || *self == TileClass::Lava || *self == TileClass::Chasm
+    }
+    //How much stepping onto a tile is worth to pathing, so the AI goes around the nasty stuff when it can
+    pub fn move_cost(&self) -> f32 {
+        match self {
+            TileClass::ShallowWater => 2.0,
+            TileClass::DeepWater => 4.0,
+            TileClass::Lava => 16.0,
+            _ => 1.0
+        }
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -23,6 +49,14 @@ pub struct Map {
     pub visible: Vec<bool>,
     pub revealed: Vec<bool>,
     pub objblocked: Vec<bool>,
+    //How brightly lit each tile is, from 0 to 1, and the colour of the light on it. Both are worked out
+    //again from the floor's light sources whenever those change, so they aren't saved.
+    #[serde(skip)]
+    pub light: Vec<f32>,
+    #[serde(skip)]
+    pub light_color: Vec<RGBA>,
+    #[serde(skip)]
+    pub light_cache: LightCache,
 }
 impl Map {
     pub fn new(w: i32, h: i32) -> Map {
@@ -33,6 +67,9 @@ impl Map {
             visible: vec![false; (w * h) as usize],
             revealed: vec![false; (w * h) as usize],
             objblocked: vec![false; (w * h) as usize],
+            light: vec![0.0; (w * h) as usize],
+            light_color: vec![RGBA::from_u8(0, 0, 0, 255); (w * h) as usize],
+            light_cache: LightCache::default(),
         }
     }
     pub fn from_copy(old_map: &Map) -> Map {
@@ -42,7 +79,10 @@ impl Map {
             tiles: old_map.tiles.to_owned(),
             visible: old_map.visible.to_owned(),
             revealed: old_map.revealed.to_owned(),
-            objblocked: old_map.objblocked.to_owned()
+            objblocked: old_map.objblocked.to_owned(),
+            light: old_map.light.to_owned(),
+            light_color: old_map.light_color.to_owned(),
+            light_cache: old_map.light_cache.clone()
         }
     }
 
@@ -66,19 +106,59 @@ impl Map {
     pub fn in_bounds(&self, x: i32, y: i32) -> bool {
         x >= 0 && x < self.width && y >= 0 && y < self.height
     }
+    //Checks whether a tile is bright enough to be seen from past a viewer's own sight range
+    pub fn is_lit(&self, pos: Point) -> bool {
+        return self.try_index(pos.x, pos.y).map_or(false, |idx| self.light.get(idx).map_or(false, |l| *l >= LIT_THRESHOLD))
+    }
     //Checks to see if the tile at an index is walkable
     pub fn walkable(&self, x: i32, y: i32) -> bool {
         let idx = self.index(x, y);
         return self.in_bounds(x, y) && !self.tiles[idx].does_collide() && !self.objblocked[idx]
     }
+    //Checks for a closed door with nothing standing in the way of opening it
+    pub fn openable(&self, x: i32, y: i32) -> bool {
+        let idx = self.index(x, y);
+        return self.in_bounds(x, y) && self.tiles[idx] == TileClass::DoorClosed && !self.objblocked[idx]
+    }
+
+    //Opens and closes doors, returning whether anything changed
+    pub fn open_door(&mut self, pos: Point) -> bool {
+        if !self.openable(pos.x, pos.y) { return false }
+        let idx = self.index(pos.x, pos.y);
+        self.tiles[idx] = TileClass::DoorOpen;
+        self.light_cache.invalidate();
+        return true
+    }
+    //Turns a secret door into a plain closed one once it's been found
+    pub fn reveal_secret(&mut self, pos: Point) -> bool {
+        let idx = match self.try_index(pos.x, pos.y) {
+            Some(idx) => idx,
+            None => return false
+        };
+        if self.tiles[idx] != TileClass::SecretDoor { return false }
+        self.tiles[idx] = TileClass::DoorClosed;
+        self.revealed[idx] = true;
+        return true
+    }
+    pub fn close_door(&mut self, pos: Point) -> bool {
+        if !self.walkable(pos.x, pos.y) { return false }
+        let idx = self.index(pos.x, pos.y);
+        if self.tiles[idx] != TileClass::DoorOpen { return false }
+        self.tiles[idx] = TileClass::DoorClosed;
+        self.light_cache.invalidate();
+        return true
+    }
 
-    fn valid_exit(&self, pos: Point, delta: Point) -> Option<usize> {
+    //Closed doors count as exits for pathing, since anything walking through can just open them.
+    //Chasms never do, and other tiles cost more the worse they are to stand on.
+    fn valid_exit(&self, pos: Point, delta: Point, base_cost: f32) -> Option<(usize, f32)> {
         let dest = pos + delta;
 
         if self.in_bounds(dest.x, dest.y) {
-            if self.walkable(dest.x, dest.y) {
+            if self.walkable(dest.x, dest.y) || self.openable(dest.x, dest.y) {
                 let idx = self.index(dest.x, dest.y);
-                return Some(idx)
+                if self.tiles[idx] == TileClass::Chasm { return None }
+                return Some((idx, base_cost * self.tiles[idx].move_cost()))
             }
         }
         return None
@@ -102,15 +182,15 @@ impl BaseMap for Map {
         let mut exits = SmallVec::new();
         let location = self.point_from_idx(idx);
 
-        if let Some(idx) = self.valid_exit(location, DL_LEFT)   { exits.push((idx, 1.0)) }
-        if let Some(idx) = self.valid_exit(location, DL_RIGHT)  { exits.push((idx, 1.0)) }
-        if let Some(idx) = self.valid_exit(location, DL_UP)     { exits.push((idx, 1.0)) }
-        if let Some(idx) = self.valid_exit(location, DL_DOWN)   { exits.push((idx, 1.0)) }
+        if let Some(exit) = self.valid_exit(location, DL_LEFT, 1.0)   { exits.push(exit) }
+        if let Some(exit) = self.valid_exit(location, DL_RIGHT, 1.0)  { exits.push(exit) }
+        if let Some(exit) = self.valid_exit(location, DL_UP, 1.0)     { exits.push(exit) }
+        if let Some(exit) = self.valid_exit(location, DL_DOWN, 1.0)   { exits.push(exit) }
 
-        if let Some(idx) = self.valid_exit(location, DL_UP + DL_LEFT)       { exits.push((idx, 1.45)) }
-        if let Some(idx) = self.valid_exit(location, DL_DOWN + DL_LEFT)     { exits.push((idx, 1.45)) }
-        if let Some(idx) = self.valid_exit(location, DL_UP + DL_RIGHT)      { exits.push((idx, 1.45)) }
-        if let Some(idx) = self.valid_exit(location, DL_DOWN + DL_RIGHT)    { exits.push((idx, 1.45)) }
+        if let Some(exit) = self.valid_exit(location, DL_UP + DL_LEFT, 1.45)       { exits.push(exit) }
+        if let Some(exit) = self.valid_exit(location, DL_DOWN + DL_LEFT, 1.45)     { exits.push(exit) }
+        if let Some(exit) = self.valid_exit(location, DL_UP + DL_RIGHT, 1.45)      { exits.push(exit) }
+        if let Some(exit) = self.valid_exit(location, DL_DOWN + DL_RIGHT, 1.45)    { exits.push(exit) }
 
         return exits
     }
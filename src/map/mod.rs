@@ -1,4 +1,10 @@
 mod generator;
 mod mapdef;
+mod profile;
+mod region;
+mod vault;
 pub use generator::*;
 pub use mapdef::*;
+pub use profile::*;
+pub use region::*;
+pub use vault::*;
@@ -0,0 +1,55 @@
+use crate::prelude::*;
+
+//How the floors in a band of depths get built: the layout, how big it is, and how much goes in it.
+//These come from the content database so progression can be tuned without touching code.
+#[derive(Clone, Copy, PartialEq)]
+pub struct LevelProfile {
+    pub mode: GenerationMode,
+    pub width: i32,
+    pub height: i32,
+    pub max_rooms: usize,
+    pub spawn_table: u32,
+    //Out of every hundred spawn regions, how many get an enemy put in them
+    pub enemy_density: i32,
+    pub item_budget: i32,
+    //How many torches get put up around the floor
+    pub light_sources: i32
+}
+impl LevelProfile {
+    //Grabs the profile for a depth, or the built-in one if the database doesn't cover it
+    pub fn for_depth(depth: i32) -> LevelProfile {
+        return LevelProfile::for_branch(MAIN_DUNGEON, depth)
+    }
+    pub fn for_branch(branch: u32, depth: i32) -> LevelProfile {
+        let conn = open_connection();
+        let profile = import_level_profile(&conn, branch, depth);
+        conn.close().expect("Connection to SQLite DB failed to close.");
+
+        return profile.unwrap_or(LevelProfile::fallback(depth))
+    }
+    //What every floor got before profiles were moved into the database
+    pub fn fallback(depth: i32) -> LevelProfile {
+        LevelProfile {
+            mode: GenerationMode::for_depth(depth),
+            width: 60,
+            height: 60,
+            max_rooms: 20,
+            spawn_table: if depth <= 3 { 1 } else { 2 },
+            enemy_density: 100,
+            item_budget: 5,
+            light_sources: 2
+        }
+    }
+
+    //The same profile with a different layout swapped in
+    pub fn with_layout(mut self, mode: GenerationMode, w: i32, h: i32) -> LevelProfile {
+        self.mode = mode;
+        self.width = w;
+        self.height = h;
+        return self
+    }
+    //How many enemies a floor with this many spawn regions gets
+    pub fn enemy_count(&self, regions: usize) -> i32 {
+        return (regions as i32 * self.enemy_density + 50) / 100
+    }
+}
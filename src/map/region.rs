@@ -0,0 +1,35 @@
+use crate::prelude::*;
+
+//An area of a map that things can be spawned into. Room layouts get one per room, while layouts
+//without rooms carve their open floor up into regions instead.
+#[derive(Clone, PartialEq)]
+pub struct SpawnRegion {
+    pub points: Vec<Point>,
+    pub center: Point
+}
+impl SpawnRegion {
+    pub fn from_rect(rect: &Rect) -> SpawnRegion {
+        let mut points: Vec<Point> = Vec::new();
+        rect.for_each(|p| points.push(p));
+        SpawnRegion { points, center: rect.center() }
+    }
+    //Builds a region from loose points, centered on whichever point is closest to their middle
+    pub fn from_points(points: Vec<Point>) -> SpawnRegion {
+        let count = points.len().max(1) as f32;
+        let mid = {
+            let (mut sx, mut sy) = (0.0, 0.0);
+            for p in points.iter() {
+                sx += p.x as f32;
+                sy += p.y as f32;
+            }
+            Point::new((sx / count).round() as i32, (sy / count).round() as i32)
+        };
+        let mut center = *points.first().unwrap_or(&Point::zero());
+        for p in points.iter() {
+            if DistanceAlg::PythagorasSquared.distance2d(*p, mid) < DistanceAlg::PythagorasSquared.distance2d(center, mid) {
+                center = *p;
+            }
+        }
+        SpawnRegion { points, center }
+    }
+}
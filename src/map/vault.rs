@@ -0,0 +1,51 @@
+use crate::prelude::*;
+
+//What a vault's spawn marker turns into, by content database ID
+#[derive(Clone, Copy, PartialEq)]
+pub enum VaultSpawn {
+    Enemy(u32),
+    Item(u32)
+}
+
+//A hand-authored room, stamped into generated levels as-is. Layouts are rows of glyphs:
+//  '#' wall, '.' floor, '"' glass, '+' closed door, ' ' leaves whatever the generator put there,
+//  and any glyph with a spawn entry is floor with that enemy or item on it.
+#[derive(Clone)]
+pub struct VaultTemplate {
+    pub name: String,
+    pub min_depth: i32,
+    pub max_depth: i32,
+    pub chance: i32,
+    pub rows: Vec<Vec<char>>,
+    pub spawns: Vec<(char, VaultSpawn)>
+}
+impl VaultTemplate {
+    pub fn new(name: String, min_depth: i32, max_depth: i32, chance: i32, layout: &str, spawns: Vec<(char, VaultSpawn)>) -> VaultTemplate {
+        let rows = layout.lines()
+            .filter(|l| !l.is_empty())
+            .map(|l| l.chars().collect())
+            .collect();
+        VaultTemplate { name, min_depth, max_depth, chance, rows, spawns }
+    }
+
+    pub fn width(&self) -> i32 { self.rows.iter().map(|r| r.len()).max().unwrap_or(0) as i32 }
+    pub fn height(&self) -> i32 { self.rows.len() as i32 }
+
+    fn glyph_at(&self, x: i32, y: i32) -> char {
+        return *self.rows.get(y as usize).and_then(|r| r.get(x as usize)).unwrap_or(&' ')
+    }
+    //The tile a layout cell stamps onto the map, or None if it leaves the map alone
+    pub fn tile_at(&self, x: i32, y: i32) -> Option<TileClass> {
+        return match self.glyph_at(x, y) {
+            '#' => Some(TileClass::Wall),
+            '.' => Some(TileClass::Floor),
+            '"' => Some(TileClass::Glass),
+            '+' => Some(TileClass::DoorClosed),
+            _ => if self.spawn_at(x, y).is_some() { Some(TileClass::Floor) } else { None }
+        }
+    }
+    pub fn spawn_at(&self, x: i32, y: i32) -> Option<VaultSpawn> {
+        let glyph = self.glyph_at(x, y);
+        return self.spawns.iter().find(|(g, _)| *g == glyph).map(|(_, s)| *s)
+    }
+}
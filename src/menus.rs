@@ -3,57 +3,164 @@ use crate::prelude::*;
 #[derive(Clone, Copy, PartialEq)]
 pub enum MenuSelection {
     NewGame,
+    NewGameSeeded,
     LoadGame,
+    WatchReplay,
     Quit,
     Continue,
-    SaveGame
+    SaveGame,
+    SaveSlot(usize),
+    LoadSlot(usize),
+    DeleteSlot(usize),
+    TogglePermadeath,
+    Back
 }
 
+#[derive(Clone, Copy, PartialEq)]
+pub enum SlotMenuMode { Save, Load }
+
 pub struct Menu {
     selections: Vec<MenuSelection>,
+    disabled: Vec<MenuSelection>,
     pub current_selection: usize,
     pub processed_selection: Option<MenuSelection>,
+    pub slots: Vec<SaveSlot>,
+    pub message: Option<String>,
+    pub permadeath: bool,
+    //Only a paused permadeath run gets saved on the way out
+    saves_on_quit: bool,
 }
 impl Menu {
-    pub fn main_menu() -> Menu {
+    pub fn main_menu(permadeath: bool) -> Menu {
         Menu {
             selections: vec![
                 MenuSelection::NewGame,
+                MenuSelection::NewGameSeeded,
                 MenuSelection::LoadGame,
+                MenuSelection::WatchReplay,
+                MenuSelection::TogglePermadeath,
                 MenuSelection::Quit,
             ],
+            disabled: Menu::get_disabled_main(),
             current_selection: 0,
             processed_selection: None,
+            slots: Vec::new(),
+            message: None,
+            permadeath,
+            saves_on_quit: false,
         }
     }
-    pub fn pause_menu() -> Menu {
-        Menu {
-            selections: vec![
+    //Permadeath runs can only be left by saving and quitting
+    pub fn pause_menu(permadeath: bool) -> Menu {
+        let selections = if permadeath {
+            vec![
+                MenuSelection::Continue,
+                MenuSelection::Quit,
+            ]
+        } else {
+            vec![
                 MenuSelection::Continue,
                 MenuSelection::SaveGame,
                 MenuSelection::LoadGame,
                 MenuSelection::Quit,
-            ],
+            ]
+        };
+
+        Menu {
+            selections,
+            disabled: Menu::get_disabled_load(),
+            current_selection: 0,
+            processed_selection: None,
+            slots: Vec::new(),
+            message: None,
+            permadeath,
+            saves_on_quit: permadeath,
+        }
+    }
+    pub fn slot_menu(mode: SlotMenuMode) -> Menu {
+        let mut selections: Vec<MenuSelection> = (0..MAX_SAVE_SLOTS)
+            .map(|i| match mode {
+                SlotMenuMode::Save => MenuSelection::SaveSlot(i),
+                SlotMenuMode::Load => MenuSelection::LoadSlot(i),
+            })
+            .collect();
+        selections.push(MenuSelection::Back);
+
+        Menu {
+            selections,
+            disabled: Vec::new(),
             current_selection: 0,
             processed_selection: None,
+            slots: list_save_slots(),
+            message: None,
+            permadeath: false,
+            saves_on_quit: false,
         }
     }
+    //Greys out the Load Game option when there is nothing to load
+    fn get_disabled_load() -> Vec<MenuSelection> {
+        if any_loadable_saves() { Vec::new() } else { vec![MenuSelection::LoadGame] }
+    }
+    fn get_disabled_main() -> Vec<MenuSelection> {
+        let mut disabled = Menu::get_disabled_load();
+        if !replay_exists() { disabled.push(MenuSelection::WatchReplay) }
+        disabled
+    }
+    //Re-reads the save directory after a slot has been changed
+    pub fn refresh_slots(&mut self) {
+        self.slots = list_save_slots();
+    }
+    pub fn get_label(&self, selection: &MenuSelection) -> String {
+        return match selection {
+            MenuSelection::NewGame => "New Game",
+            MenuSelection::NewGameSeeded => "New Game with Seed",
+            MenuSelection::LoadGame => "Load Game",
+            MenuSelection::WatchReplay => "Watch Last Replay",
+            MenuSelection::Continue => "Continue",
+            MenuSelection::SaveGame => "Save Game",
+            MenuSelection::Quit => if self.saves_on_quit { "Save and Quit" } else { "Quit" },
+            MenuSelection::TogglePermadeath => if self.permadeath { "Permadeath: On" } else { "Permadeath: Off" },
+            _ => "Back"
+        }.to_string()
+    }
+    pub fn is_enabled(&self, index: usize) -> bool {
+        !self.disabled.contains(&self.selections[index])
+    }
     pub fn cycle_selection_down(&mut self) {
-        if self.current_selection != self.selections.len() - 1 {
-            self.current_selection += 1
-        } else {
-            self.current_selection = 0
+        loop {
+            if self.current_selection != self.selections.len() - 1 {
+                self.current_selection += 1
+            } else {
+                self.current_selection = 0
+            }
+            if self.is_enabled(self.current_selection) { break }
         }
     }
     pub fn cycle_selection_up(&mut self) {
-        if self.current_selection != 0 {
-            self.current_selection -= 1
-        } else {
-            self.current_selection = self.selections.len() - 1
+        loop {
+            if self.current_selection != 0 {
+                self.current_selection -= 1
+            } else {
+                self.current_selection = self.selections.len() - 1
+            }
+            if self.is_enabled(self.current_selection) { break }
         }
     }
     pub fn process_selection(&mut self) {
-        self.processed_selection = Some(self.selections[self.current_selection])
+        if self.is_enabled(self.current_selection) {
+            self.processed_selection = Some(self.selections[self.current_selection])
+        }
+    }
+    //Flags the currently highlighted save slot for deletion
+    pub fn process_delete(&mut self) {
+        match self.selections[self.current_selection] {
+            MenuSelection::SaveSlot(i) | MenuSelection::LoadSlot(i) => {
+                if !self.slots[i].is_empty() {
+                    self.processed_selection = Some(MenuSelection::DeleteSlot(i))
+                }
+            },
+            _ => {}
+        }
     }
 }
 
@@ -71,83 +178,154 @@ pub fn batch_main_menu(menu: &Menu) {
         ColorPair::new(WHITE, BLACK),
     );
 
+    batch_menu_options(menu, &mut textbatch, Point::new(CONSOLE_W - 10, CONSOLE_H / 4 + 3));
+
+    if let Some(message) = &menu.message {
+        textbatch.print_color_centered(CONSOLE_H / 4 + 5 + menu.selections.len() as i32 * 2, message, ColorPair::new(RED, BLACK));
+    }
+
+    bgbatch.fill_region(Rect::with_exact(0,0,CONSOLE_W * 2,CONSOLE_H), ColorPair::new(BLACK,BLACK), 0);
+    bgbatch.submit(0).expect("Failed to batch menu draw");
+    textbatch.submit(0).expect("Failed to batch menu draw");
+}
+pub fn batch_pause_menu(menu: &Menu) {
+    let mut textbatch = DrawBatch::new();
+    textbatch.target(TXT_LAYER);
+
+    batch_menu_options(menu, &mut textbatch, Point::new(CONSOLE_W / 2 - 5, CONSOLE_H / 4 + 1));
+
+    textbatch.submit(0).expect("Failed to batch menu draw");
+}
+//Prints each of a menu's options in a column, two lines apart
+fn batch_menu_options(menu: &Menu, textbatch: &mut DrawBatch, start: Point) {
     let unselected: ColorPair = ColorPair::new(WHITE, BLACK);
     let selected: ColorPair = ColorPair::new(YELLOW, GREY10);
+    let disabled: ColorPair = ColorPair::new(GREY40, BLACK);
 
-    let mut newgame_pair: ColorPair = unselected;
-    let mut loadgame_pair: ColorPair = unselected;
-    let mut quit_pair: ColorPair = unselected;
+    for (i, selection) in menu.selections.iter().enumerate() {
+        let color = if menu.current_selection == i { selected }
+                    else if menu.is_enabled(i) { unselected }
+                    else { disabled };
 
-    if menu.current_selection == 0 {
-        newgame_pair = selected;
-    } else if menu.current_selection == 1 {
-        loadgame_pair = selected;
-    } else if menu.current_selection == 2 {
-        quit_pair = selected;
+        textbatch.print_color(start + Point::new(0, i as i32 * 2), menu.get_label(selection), color);
     }
+}
+pub fn batch_slot_menu(menu: &Menu) {
+    let mut bgbatch = DrawBatch::new();
+    let mut textbatch = DrawBatch::new();
+    bgbatch.target(OBJ_LAYER);
+    textbatch.target(TXT_LAYER);
 
-    textbatch.print_color(
-        Point::new(CONSOLE_W - 10, CONSOLE_H / 4 + 3),
-        "New Game",
-        newgame_pair,
-    );
-    textbatch.print_color(
-        Point::new(CONSOLE_W - 10, CONSOLE_H / 4 + 5),
-        "Load Game",
-        loadgame_pair,
-    );
-    textbatch.print_color(
-        Point::new(CONSOLE_W - 10, CONSOLE_H / 4 + 7),
-        "Quit",
-        quit_pair,
+    let unselected: ColorPair = ColorPair::new(WHITE, BLACK);
+    let empty: ColorPair = ColorPair::new(GREY50, BLACK);
+    let error: ColorPair = ColorPair::new(RED, BLACK);
+    let selected: ColorPair = ColorPair::new(YELLOW, GREY10);
+
+    let title = match menu.selections[0] {
+        MenuSelection::SaveSlot(_) => "Save Game",
+        _ => "Load Game"
+    };
+    textbatch.print_color_centered(CONSOLE_H / 4 - 2, title, ColorPair::new(RED, BLACK));
+
+    let mut y = CONSOLE_H / 4 + 1;
+    for (i, selection) in menu.selections.iter().enumerate() {
+        let (text, mut color) = match selection {
+            MenuSelection::SaveSlot(id) | MenuSelection::LoadSlot(id) => {
+                let slot = &menu.slots[*id];
+                match &slot.meta {
+                    Some(meta) => (
+                        format!("{}: Depth {}, Turn {}, HP {}/{} - {}{}{}",
+                                slot.get_name(), meta.depth, meta.turns, meta.health.0, meta.health.1, format_timestamp(meta.timestamp),
+                                if meta.permadeath { " (Permadeath)" } else { "" },
+                                if meta.replayable { "" } else { " (No replay)" }),
+                        unselected
+                    ),
+                    None => match &slot.error {
+                        Some(_) => (format!("{}: Unreadable save", slot.get_name()), error),
+                        None => (format!("{}: Empty", slot.get_name()), empty)
+                    }
+                }
+            },
+            _ => (String::from("Back"), unselected)
+        };
+        if menu.current_selection == i { color = selected; }
+
+        textbatch.print_color(Point::new(CONSOLE_W / 2 - 20, y), text, color);
+        y += 2;
+    }
+
+    if let Some(message) = &menu.message {
+        textbatch.print_color_centered(y + 1, message, error);
+    }
+
+    textbatch.print_color_centered(
+        CONSOLE_H - 3,
+        "Enter to select, D to delete, ESC to go back",
+        ColorPair::new(GOLD4, BLACK),
     );
 
     bgbatch.fill_region(Rect::with_exact(0,0,CONSOLE_W * 2,CONSOLE_H), ColorPair::new(BLACK,BLACK), 0);
     bgbatch.submit(0).expect("Failed to batch menu draw");
     textbatch.submit(0).expect("Failed to batch menu draw");
 }
-pub fn batch_pause_menu(menu: &Menu) {
+pub fn batch_seed_entry(input: &str) {
+    let mut bgbatch = DrawBatch::new();
     let mut textbatch = DrawBatch::new();
+    bgbatch.target(OBJ_LAYER);
     textbatch.target(TXT_LAYER);
 
-    let unselected: ColorPair = ColorPair::new(WHITE, BLACK);
-    let selected: ColorPair = ColorPair::new(YELLOW, GREY10);
+    textbatch.print_color_centered(CONSOLE_H / 4, "Enter a Seed", ColorPair::new(RED, BLACK));
+    textbatch.print_color_centered(CONSOLE_H / 4 + 3, format!("{}_", input), ColorPair::new(YELLOW, GREY10));
 
-    let mut continue_pair: ColorPair = unselected;
-    let mut savegame_pair: ColorPair = unselected;
-    let mut loadgame_pair: ColorPair = unselected;
-    let mut quit_pair: ColorPair = unselected;
-
-    if menu.current_selection == 0 {
-        continue_pair = selected;
-    } else if menu.current_selection == 1 {
-        savegame_pair = selected;
-    } else if menu.current_selection == 2 {
-        loadgame_pair = selected;
-    } else if menu.current_selection == 3 {
-        quit_pair = selected;
-    }
-
-    textbatch.print_color(
-        Point::new(CONSOLE_W / 2 - 5, CONSOLE_H / 4 + 1),
-        "Continue",
-        continue_pair,
-    );
-    textbatch.print_color(
-        Point::new(CONSOLE_W / 2 - 5, CONSOLE_H / 4 + 3),
-        "Save Game",
-        savegame_pair,
-    );
-    textbatch.print_color(
-        Point::new(CONSOLE_W / 2 - 5, CONSOLE_H / 4 + 5),
-        "Load Game",
-        loadgame_pair,
+    textbatch.print_color_centered(
+        CONSOLE_H - 3,
+        "Enter to begin (blank for random), ESC to go back",
+        ColorPair::new(GOLD4, BLACK),
     );
-    textbatch.print_color(
-        Point::new(CONSOLE_W / 2 - 5, CONSOLE_H / 4 + 7),
-        "Quit",
-        quit_pair,
+
+    bgbatch.fill_region(Rect::with_exact(0,0,CONSOLE_W * 2,CONSOLE_H), ColorPair::new(BLACK,BLACK), 0);
+    bgbatch.submit(0).expect("Failed to batch menu draw");
+    textbatch.submit(0).expect("Failed to batch menu draw");
+}
+pub fn batch_victory_screen(summary: &RunSummary) {
+    let mut bgbatch = DrawBatch::new();
+    let mut textbatch = DrawBatch::new();
+    bgbatch.target(OBJ_LAYER);
+    textbatch.target(TXT_LAYER);
+
+    let label: ColorPair = ColorPair::new(GREY65, BLACK);
+    let value: ColorPair = ColorPair::new(WHITE, BLACK);
+
+    textbatch.print_color_centered(CONSOLE_H / 4, "Victory!", ColorPair::new(GOLD, BLACK));
+    if let Some(goal) = &summary.goal {
+        textbatch.print_color_centered(CONSOLE_H / 4 + 2, format!("You have claimed the {}.", goal), value);
+    }
+
+    let branches = if summary.branches_explored.is_empty() { String::from("None") }
+                   else { summary.branches_explored.join(", ") };
+    let lines = [
+        ("Turns taken", summary.turns.to_string()),
+        ("Deepest level", summary.deepest.to_string()),
+        ("Floors explored", summary.floors_explored.to_string()),
+        ("Branches explored", branches),
+        ("Health left", format!("{}/{}", summary.health.0, summary.health.1)),
+        ("Seed", summary.seed.to_string()),
+        ("Permadeath", String::from(if summary.permadeath { "On" } else { "Off" })),
+    ];
+    let x = CONSOLE_W - 16;
+    for (i, (name, text)) in lines.iter().enumerate() {
+        let y = CONSOLE_H / 4 + 5 + i as i32 * 2;
+        textbatch.print_color(Point::new(x, y), format!("{}:", name), label);
+        textbatch.print_color(Point::new(x + 20, y), text, value);
+    }
+
+    textbatch.print_color_centered(
+        CONSOLE_H - 3,
+        "Enter or R to return to the main menu",
+        ColorPair::new(GOLD4, BLACK),
     );
 
+    bgbatch.fill_region(Rect::with_exact(0,0,CONSOLE_W * 2,CONSOLE_H), ColorPair::new(BLACK,BLACK), 0);
+    bgbatch.submit(0).expect("Failed to batch menu draw");
     textbatch.submit(0).expect("Failed to batch menu draw");
-}
\ No newline at end of file
+}
@@ -2,6 +2,8 @@ use crate::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 
+//A template for an entity, used by spawning and the content database. Spawning one into
+//the world breaks it up into components; see Ecs::spawn.
 #[derive(Default, Serialize, Deserialize, Clone)]
 pub struct Object {
     pub name: Option<String>,
@@ -12,6 +14,7 @@ pub struct Object {
     pub player_mem: PlayerMemory,
     pub viewshed: Option<Viewshed>,
     pub block_tile: bool,
+    pub goal: bool,
     pub initiative: Option<u8>,
 
     pub in_inventory: Option<InInventory>,
@@ -21,7 +24,9 @@ pub struct Object {
     pub damage: Option<Damage>,
 
     pub ai: Option<AIClass>,
-    pub item_stats: Option<ItemStats>
+    pub item_stats: Option<ItemStats>,
+    pub trap: Option<Trap>,
+    pub light: Option<LightSource>
 }
 impl Object {
     pub fn blank() -> Object {
@@ -50,7 +55,7 @@ impl Default for ActorTag {
 
 #[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct Render {
-    pub glyph: FontCharType,
+    pub glyph: u16,
     pub color: ColorPair,
     pub order: u8
 }
@@ -160,6 +165,41 @@ impl Clone for Damage {
     }
 }
 
+//Something hidden on a tile that goes off when anything steps onto it
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TrapKind {
+    Dart,
+    Teleport,
+    Alarm,
+    Trapdoor
+}
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct Trap {
+    pub kind: TrapKind,
+    pub revealed: bool,
+    pub triggered_by: Option<EntityId>
+}
+impl Trap {
+    pub fn new(kind: TrapKind) -> Trap {
+        Trap { kind, revealed: false, triggered_by: None }
+    }
+}
+
+//Something that lights up the tiles around it, or around whatever is carrying it
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct LightSource {
+    pub radius: i32,
+    pub color: RGBA
+}
+
+//Marks an entity as something others can't walk through
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct BlocksTile;
+
+//Marks the item a run is won by retrieving
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct GoalItem;
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct PlayerMemory {
     pub seen: bool,
@@ -171,7 +211,7 @@ impl Default for PlayerMemory {
 
 #[derive(Serialize,Deserialize,Clone)]
 pub struct InInventory {
-    pub owner_id: usize
+    pub owner_id: EntityId
 }
 #[derive(Clone,Copy,Serialize,Deserialize,Eq,PartialEq,Hash)]
 pub enum EquipSlot {
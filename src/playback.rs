@@ -0,0 +1,76 @@
+use crate::prelude::*;
+
+//Feeds recorded actions into the same turn pipeline the player's input uses
+pub fn advance_replay(gs: &mut State, frame_time_ms: f32) {
+    //Let the systems catch up on the last action before feeding in another
+    if gs.game.proc { return }
+
+    let steps = gs.replay.as_mut().unwrap().steps_due(frame_time_ms);
+
+    for _ in 0..steps {
+        if gs.game.turn_state == TurnState::GameOver || gs.game.turn_state == TurnState::Victory { break }
+
+        match gs.replay.as_mut().unwrap().next_action() {
+            Some(action) => {
+                gs.game.process_action(action);
+                gs.exec_all_systems();
+            },
+            None => break
+        }
+    }
+
+    if steps > 0 {
+        let player = gs.replay.as_ref().unwrap();
+        if player.is_finished() || gs.game.turn_state == TurnState::GameOver || gs.game.turn_state == TurnState::Victory {
+            gs.replay.as_mut().unwrap().paused = true;
+            gs.game.logs.update_logs(LogMessage::new()
+                .add_part("The replay has finished. Press", ColorPair::new(WHITE, GREY10))
+                .add_part("ESC", ColorPair::new(LIME_GREEN, GREY10))
+                .add_part("to return to the main menu.", ColorPair::new(WHITE, GREY10))
+            );
+        }
+        gs.refresh_con = true;
+    }
+}
+
+pub fn replay_input(gs: &mut State, con: &BTerm) {
+    if let Some(key) = con.key {
+        let player = gs.replay.as_mut().unwrap();
+        match key {
+            VirtualKeyCode::Space => player.paused = !player.paused,
+            VirtualKeyCode::Period | VirtualKeyCode::Right => {
+                player.paused = true;
+                player.step_requested = true;
+            },
+            VirtualKeyCode::F => player.fast_forward = !player.fast_forward,
+            VirtualKeyCode::Escape => {
+                gs.replay = None;
+                gs.menu = Some(Menu::main_menu(gs.permadeath));
+                gs.con_status = ContextStatus::MainMenu;
+                gs.game.turn_state = TurnState::Player;
+            },
+            _ => {}
+        }
+        gs.refresh_con = true;
+    }
+}
+
+pub fn batch_replay_status(player: &ReplayPlayer) {
+    let mut textbatch = DrawBatch::new();
+    textbatch.target(TXT_LAYER);
+
+    let x = CONSOLE_W * 2 - UI_CUTOFF.x * 2 + 4;
+    let state = if player.is_finished() { "Finished" }
+                else if player.paused { "Paused" }
+                else if player.fast_forward { "Fast-forward" }
+                else { "Playing" };
+
+    textbatch.print_color(Point::new(x, 11), "REPLAY", ColorPair::new(BLACK, GOLD));
+    textbatch.print(Point::new(x, 12), format!("{} ({}/{})", state, player.position, player.len()));
+    textbatch.print_color(Point::new(x, 14), "Space: Pause", ColorPair::new(GREY65, BLACK));
+    textbatch.print_color(Point::new(x, 15), ".: Step", ColorPair::new(GREY65, BLACK));
+    textbatch.print_color(Point::new(x, 16), "F: Fast-forward", ColorPair::new(GREY65, BLACK));
+    textbatch.print_color(Point::new(x, 17), "ESC: Stop", ColorPair::new(GREY65, BLACK));
+
+    textbatch.submit(15500).expect("Failed to batch replay status draw");
+}
@@ -1,15 +1,19 @@
 use crate::prelude::*;
 
+//How far a fully lit tile's colours get pulled towards the colour of the light on it
+const LIGHT_TINT_FG: f32 = 0.5;
+const LIGHT_TINT_BG: f32 = 0.3;
+
 //Runs all draw batching functions;
-pub fn batch_all(map: &Map, camera: &Camera, objects: &Vec<Object>, logs: &LogBuffer, floor: i32, mouse_pos: Point) {
-    batch_map_draws(map, camera);
-    batch_entity_draws(objects, map, camera, floor);
+pub fn batch_all(world: &World, logs: &LogBuffer, mouse_pos: Point) {
+    batch_map_draws(&world.active_map, &world.camera, world.current_branch().map(|b| &b.palette));
+    batch_entity_draws(&world.ecs, &world.active_map, &world.camera, world.floor());
     batch_mouse_area(mouse_pos);
-    batch_ui_draws(&objects[0], logs);
+    batch_ui_draws(world, logs);
 }
 
 //Adds all map tiles to the rendering batch.
-fn batch_map_draws(map: &Map, camera: &Camera) {
+fn batch_map_draws(map: &Map, camera: &Camera, palette: Option<&TilePalette>) {
     let mut batch = DrawBatch::new();
     batch.target(OBJ_LAYER);
 
@@ -22,8 +26,8 @@ fn batch_map_draws(map: &Map, camera: &Camera) {
                 let idx = map.index(x,y);
                 
                 let (glyph, colors) = match (map.visible[idx], map.revealed[idx]) {
-                    (true, _)       =>  {get_tile_render(&map.tiles[idx], false)},
-                    (false, true)   =>  {get_tile_render(&map.tiles[idx], true)},
+                    (true, _)       =>  {apply_light(get_tile_render(&map.tiles[idx], palette, false), map, idx)},
+                    (false, true)   =>  {get_tile_render(&map.tiles[idx], palette, true)},
                     (false, false)  =>  {(0,ColorPair::new(BLACK,BLACK))},
                 };
 
@@ -38,39 +42,38 @@ fn batch_map_draws(map: &Map, camera: &Camera) {
 }
 
 //Adds all visible entity renderables to the rendering batch.
-fn batch_entity_draws(objects: &Vec<Object>, map: &Map, camera: &Camera, floor: i32) {
+fn batch_entity_draws(ecs: &Ecs, map: &Map, camera: &Camera, floor: i32) {
     let mut batch = DrawBatch::new();
     batch.target(OBJ_LAYER);
     let offset = Point::new(camera.min_x, camera.min_y);
 
-    //Grab all objects that are drawable and have a position (force the player in at the end)
-    let mut render_list: Vec<(&Object, bool)> = Vec::new();
-    for object in objects.iter() {
-        if object.pos.is_some() && object.render.is_some() {
-            let pos = object.pos.as_ref().unwrap();
+    //Grab all entities that are drawable and have a position
+    let mut render_list: Vec<(EntityId, &Render, bool)> = Vec::new();
+    for (id, pos, render) in ecs.positions.join(&ecs.renders) {
+        let seen = ecs.memories.get(id).map_or(false, |mem| mem.seen);
+        if ecs.is_hidden(id) || ecs.get_floor(id) != floor { continue }
+        if pos.x > camera.min_x && pos.x < camera.max_x && pos.y > camera.min_y && pos.y < camera.max_y {
             let idx = map.index(pos.x, pos.y);
-            if pos.x > camera.min_x && pos.x < camera.max_x && pos.y > camera.min_y && pos.y < camera.max_y && object.floor == floor {
-                if map.visible[idx] {
-                    render_list.push((object, true))
-                } else if map.revealed[idx] && object.player_mem.seen {
-                    render_list.push((object, false))
-                }
+            if map.visible[idx] && !hidden_in_dark(ecs, map, id) {
+                render_list.push((id, render, true))
+            } else if map.revealed[idx] && seen {
+                render_list.push((id, render, false))
             }
         }
     }
 
-    render_list.sort_by_key(|o| o.0.render.as_ref().unwrap().order);
+    render_list.sort_by_key(|o| o.1.order);
     for obj in render_list.iter() {
         let pos: Point;
         let mut render: Render;
 
-        if obj.1 {
-            pos = obj.0.pos.unwrap();
-            render = obj.0.render.unwrap();
+        if obj.2 {
+            pos = *ecs.positions.get(obj.0).unwrap();
+            render = *obj.1;
         }
         else {
-            pos = obj.0.player_mem.last_pos.unwrap();
-            render = obj.0.render.unwrap();
+            pos = ecs.memories.get(obj.0).unwrap().last_pos.unwrap();
+            render = *obj.1;
             render.color.fg = render.color.fg.desaturate();
             render.color.bg = render.color.bg.desaturate();
         }
@@ -88,7 +91,8 @@ fn batch_mouse_area(pos: Point) {
     batch.submit(5050).expect("Failed to batch mouse draw");
 }
 
-fn batch_ui_draws(player: &Object, logs: &LogBuffer) {
+fn batch_ui_draws(world: &World, logs: &LogBuffer) {
+    let ecs = &world.ecs;
     let mut uibatch = DrawBatch::new();
     let mut textbatch = DrawBatch::new();
     uibatch.target(OBJ_LAYER);
@@ -98,15 +102,15 @@ fn batch_ui_draws(player: &Object, logs: &LogBuffer) {
     uibatch.draw_double_box(Rect::with_size(CONSOLE_W - UI_CUTOFF.x, 0, UI_CUTOFF.x - 1, CONSOLE_H - 1), ColorPair::new(GREY75, BLACK));
     textbatch.print(Point::new(CONSOLE_W * 2 - UI_CUTOFF.x * 2 + 4, 0), "Stats");
 
-    if let Some(ActorTag::Player) = player.tag {
-        let health = player.health.as_ref().unwrap().current;
-        let max = player.health.as_ref().unwrap().max;
+    if let (Some(ActorTag::Player), Some(player_health), Some(damage)) = (ecs.tags.get(PLAYER), ecs.healths.get(PLAYER), ecs.damages.get(PLAYER)) {
+        let health = player_health.current;
+        let max = player_health.max;
 
         let dmg_string = {
-            let mut s = format!("{}d{}", player.damage.as_ref().unwrap().dice, player.damage.as_ref().unwrap().val);
-            if !player.damage.as_ref().unwrap().modifiers.is_empty() {
+            let mut s = format!("{}d{}", damage.dice, damage.val);
+            if !damage.modifiers.is_empty() {
                 let mut total = 0;
-                for i in player.damage.as_ref().unwrap().modifiers.iter() {
+                for i in damage.modifiers.iter() {
                     total += i;
                 }
                 s.push_str(format!(" + {}", total).as_str());
@@ -127,26 +131,64 @@ fn batch_ui_draws(player: &Object, logs: &LogBuffer) {
         textbatch.print(Point::new(CONSOLE_W * 2 - UI_CUTOFF.x * 2 + 4, 6), dmg_string);
     }
 
+    textbatch.print(Point::new(CONSOLE_W * 2 - UI_CUTOFF.x * 2 + 4, 8), "Depth:");
+    let depth = match world.current_branch() {
+        Some(branch) => format!("{} {}", branch.name, world.depth),
+        None => format!("{}", world.depth)
+    };
+    textbatch.print(Point::new(CONSOLE_W * 2 - UI_CUTOFF.x * 2 + 4, 9), depth);
+
+    textbatch.print(Point::new(CONSOLE_W * 2 - UI_CUTOFF.x * 2 + 4, 11), "Seed:");
+    textbatch.print_color(Point::new(CONSOLE_W * 2 - UI_CUTOFF.x * 2 + 4, 12), world.seed, ColorPair::new(GREY65, BLACK));
+
     //Draw the log box
     uibatch.draw_double_box(Rect::with_size(0, CONSOLE_H - UI_CUTOFF.y, CONSOLE_W - UI_CUTOFF.x - 1, UI_CUTOFF.y - 1), ColorPair::new(GREY75, BLACK));
     textbatch.print(Point::new(12, CONSOLE_H - UI_CUTOFF.y), "Logs");
 
     let mut tb = TextBlock::new(2, CONSOLE_H - UI_CUTOFF.y + 1, CONSOLE_W * 2 - UI_CUTOFF.x * 2 - 4, UI_CUTOFF.y - 2);
-    tb.print(&logs.format());
+    tb.print(&format_logs(logs));
     tb.render_to_draw_batch(&mut *textbatch);
 
     uibatch.submit(10000).expect("Failed to batch UI draw");
     textbatch.submit(15000).expect("Failed to batch UI draw");
 }
 
-//Returns glyph and color pair info for a tile.
-//TODO: Make tuple globals for map theming.
-fn get_tile_render(tile: &TileClass, greyscale: bool) -> (FontCharType, ColorPair) {
-    let (chr, mut color) = match tile {
-        TileClass::Floor        =>  (46, ColorPair::new(WHITE,BLACK)),
-        TileClass::Wall         =>  (176, ColorPair::new(CHOCOLATE4, BLACK)),
-        TileClass::DownStair    =>  (62, ColorPair::new(GREY70,GREY99)),
-        _                       =>  (0, ColorPair::new(WHITE,BLACK))
+//Builds the log box text, fading each older message's background a little further
+fn format_logs(logs: &LogBuffer) -> TextBuilder {
+    let mut builder = TextBuilder::empty();
+
+    for (n, message) in logs.iter().rev().enumerate() {
+        for (i, part) in message.parts.iter().enumerate() {
+            let bg = message.colors[i].bg;
+            builder.bg(bg - RGBA::from_f32(0.01,0.01,0.01,0.0) * n as f32);
+            builder.fg(message.colors[i].fg);
+            builder.append(part);
+        }
+    }
+
+    return builder;
+}
+
+//Returns glyph and color pair info for a tile. Branches swap in their own look for walls and floors.
+fn get_tile_render(tile: &TileClass, palette: Option<&TilePalette>, greyscale: bool) -> (FontCharType, ColorPair) {
+    let (chr, mut color) = match (tile.looks_like(), palette) {
+        (TileClass::Wall, Some(p))  =>  p.wall,
+        (TileClass::Floor, Some(p)) =>  p.floor,
+        (tile, _) => match tile {
+            TileClass::Floor        =>  (46, ColorPair::new(WHITE,BLACK)),
+            TileClass::Wall         =>  (176, ColorPair::new(CHOCOLATE4, BLACK)),
+            TileClass::Glass        =>  (177, ColorPair::new(LIGHTCYAN, DARKSLATEGRAY)),
+            TileClass::UpStair      =>  (60, ColorPair::new(GREY70,GREY99)),
+            TileClass::DownStair    =>  (62, ColorPair::new(GREY70,GREY99)),
+            TileClass::BranchStair  =>  (62, ColorPair::new(BLACK,GOLD)),
+            TileClass::DoorClosed   =>  (43, ColorPair::new(CHOCOLATE, BLACK)),
+            TileClass::DoorOpen     =>  (39, ColorPair::new(CHOCOLATE, BLACK)),
+            TileClass::ShallowWater =>  (126, ColorPair::new(LIGHTBLUE, BLACK)),
+            TileClass::DeepWater    =>  (247, ColorPair::new(DODGERBLUE, NAVY)),
+            TileClass::Lava         =>  (247, ColorPair::new(YELLOW, ORANGE_RED)),
+            TileClass::Chasm        =>  (250, ColorPair::new(DARKSLATEBLUE, BLACK)),
+            _                       =>  (0, ColorPair::new(WHITE,BLACK))
+        }
     };
     if greyscale {
         color.fg = color.fg.desaturate() - RGBA::from_u8(100, 100, 100, 0);
@@ -155,22 +197,32 @@ fn get_tile_render(tile: &TileClass, greyscale: bool) -> (FontCharType, ColorPai
     (chr, color)
 }
 
+//Tints a visible tile towards the colour of whatever is lighting it
+fn apply_light(tile: (FontCharType, ColorPair), map: &Map, idx: usize) -> (FontCharType, ColorPair) {
+    let (chr, mut color) = tile;
+    if let (Some(light), Some(tint)) = (map.light.get(idx), map.light_color.get(idx)) {
+        if *light > 0.0 {
+            color.fg = color.fg.lerp(*tint, light * LIGHT_TINT_FG);
+            color.bg = color.bg.lerp(*tint, light * LIGHT_TINT_BG);
+        }
+    }
+    (chr, color)
+}
+
 /*
 //Uncomment this for heatmap visual testing because it's a pain in the ass to debug through the console
 
-fn batch_heatmaps_test(objects: &Vec<Object>, map: &Map, camera: &Camera, floor: i32) {
+fn batch_heatmaps_test(ecs: &Ecs, map: &Map, camera: &Camera, floor: i32) {
     let mut batch = DrawBatch::new();
     batch.target(1);
     let offset = Point::new(camera.min_x, camera.min_y);
 
-    for obj in objects.iter() {
-        if let Some(ai) = &obj.ai {
-            for node in ai.tgt_heatmap.nodes.iter() {
-                batch.set(*node - offset, ColorPair::new(YELLOW, BLACK), to_cp437('!'));
-            }
-            for node in ai.tgt_heatmap.old_nodes.iter() {
-                batch.set(*node - offset, ColorPair::new(ORANGE, BLACK), to_cp437('?'));
-            }
+    for (_, ai) in ecs.ais.iter() {
+        for node in ai.tgt_heatmap.nodes.iter() {
+            batch.set(*node - offset, ColorPair::new(YELLOW, BLACK), to_cp437('!'));
+        }
+        for node in ai.tgt_heatmap.old_nodes.iter() {
+            batch.set(*node - offset, ColorPair::new(ORANGE, BLACK), to_cp437('?'));
         }
     }
 
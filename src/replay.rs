@@ -0,0 +1,124 @@
+use crate::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::fs::*;
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
+
+pub const REPLAY_DIR: &str = "replays";
+const REPLAY_FILE: &str = "replays/last.replay";
+
+//Milliseconds between actions during normal speed playback, and actions per frame while fast-forwarding
+const PLAYBACK_STEP_MS: f32 = 150.0;
+const FAST_FORWARD_STEPS: usize = 10;
+
+//A replay file is this header followed by a stream of CBOR-encoded actions
+#[derive(Serialize, Deserialize)]
+struct ReplayHeader {
+    seed: u64,
+    permadeath: bool
+}
+
+//Appends every action the player takes to the replay file as it happens, so a crash still leaves a usable replay
+pub struct ReplayRecorder {
+    writer: BufWriter<File>
+}
+impl ReplayRecorder {
+    pub fn start(seed: u64, permadeath: bool) -> Result<ReplayRecorder, String> {
+        let file = create_dir_all(REPLAY_DIR).and_then(|_| File::create(REPLAY_FILE))
+            .map_err(|e| format!("Could not start recording a replay ({}).", e))?;
+        let mut recorder = ReplayRecorder { writer: BufWriter::new(file) };
+
+        recorder.write(&ReplayHeader { seed, permadeath })?;
+        return Ok(recorder)
+    }
+    //Picks the recording of a reloaded run back up, writing out everything played before it was saved
+    pub fn resume(seed: u64, permadeath: bool, actions: &[Actions]) -> Result<ReplayRecorder, String> {
+        let mut recorder = ReplayRecorder::start(seed, permadeath)?;
+        for action in actions.iter() {
+            recorder.record(action)?;
+        }
+        return Ok(recorder)
+    }
+    pub fn record(&mut self, action: &Actions) -> Result<(), String> {
+        self.write(action)
+    }
+    fn write<T: Serialize>(&mut self, value: &T) -> Result<(), String> {
+        serde_cbor::to_writer(&mut self.writer, value)
+            .map_err(|e| e.to_string())
+            .and_then(|_| self.writer.flush().map_err(|e| e.to_string()))
+            .map_err(|e| format!("Could not write to the replay file ({}).", e))
+    }
+}
+
+pub fn replay_exists() -> bool {
+    Path::new(REPLAY_FILE).exists()
+}
+
+pub struct ReplayPlayer {
+    pub seed: u64,
+    pub permadeath: bool,
+    actions: Vec<Actions>,
+    pub position: usize,
+    pub paused: bool,
+    pub fast_forward: bool,
+    pub step_requested: bool,
+    timer: f32
+}
+impl ReplayPlayer {
+    pub fn load() -> Result<ReplayPlayer, String> {
+        let mut raw_data = Vec::new();
+        File::open(REPLAY_FILE)
+            .and_then(|mut f| f.read_to_end(&mut raw_data))
+            .map_err(|e| format!("Could not open the replay file ({}).", e))?;
+
+        let mut stream = serde_cbor::Deserializer::from_slice(&raw_data).into_iter::<serde_cbor::Value>();
+        let header: ReplayHeader = match stream.next() {
+            Some(Ok(value)) => serde_cbor::value::from_value(value).map_err(|e| format!("The replay file is corrupt ({}).", e))?,
+            _ => return Err(String::from("The replay file is empty."))
+        };
+
+        //A partially written trailing action is dropped rather than failing the whole replay
+        let mut actions = Vec::new();
+        for value in stream {
+            match value.map(serde_cbor::value::from_value::<Actions>) {
+                Ok(Ok(action)) => actions.push(action),
+                _ => break
+            }
+        }
+
+        Ok(ReplayPlayer {
+            seed: header.seed,
+            permadeath: header.permadeath,
+            actions,
+            position: 0,
+            paused: false,
+            fast_forward: false,
+            step_requested: false,
+            timer: 0.0
+        })
+    }
+    pub fn is_finished(&self) -> bool { self.position >= self.actions.len() }
+    pub fn len(&self) -> usize { self.actions.len() }
+    pub fn next_action(&mut self) -> Option<Actions> {
+        let action = self.actions.get(self.position).copied();
+        if action.is_some() { self.position += 1 }
+        return action
+    }
+
+    //Works out how many recorded actions should be fed in this frame
+    pub fn steps_due(&mut self, frame_time_ms: f32) -> usize {
+        if self.is_finished() { return 0 }
+        if self.step_requested {
+            self.step_requested = false;
+            return 1
+        }
+        if self.paused { return 0 }
+        if self.fast_forward { return FAST_FORWARD_STEPS }
+
+        self.timer += frame_time_ms;
+        return if self.timer >= PLAYBACK_STEP_MS {
+            self.timer = 0.0;
+            1
+        } else { 0 }
+    }
+}
@@ -1,16 +1,513 @@
 use crate::prelude::*;
+use serde::{Serialize, Deserialize};
+use serde_cbor::Value;
+use std::collections::BTreeMap;
+use std::fmt;
 use std::fs::*;
-use std::io::*;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-pub fn export_world(world: &World) {
-    let raw_data = serde_cbor::ser::to_vec(world).expect("Could not serialize world data!");
-    let mut file = File::create("saves/current.save").expect("Could not create save file!");
-    file.write_all(&*raw_data)
-        .expect("Could not write to file!");
+pub const SAVE_DIR: &str = "saves";
+pub const MAX_SAVE_SLOTS: usize = 6;
+
+//Every save starts with these bytes followed by a little-endian u16 format version
+const SAVE_MAGIC: [u8; 4] = *b"DDSV";
+const HEADER_LEN: usize = 6;
+
+//Bump SAVE_VERSION and add a migration to the list below whenever World's serialized layout changes
+pub const SAVE_VERSION: u16 = 13;
+
+//Each migration upgrades the payload by one version; the last entry produces SAVE_VERSION.
+//Dropping migrations off the front of the list raises the oldest version that can still be loaded.
+type Migration = fn(Value) -> Result<Value, SaveError>;
+const MIGRATIONS: &[Migration] = &[
+    migrate_v0_to_v1,
+    migrate_v1_to_v2,
+    migrate_v2_to_v3,
+    migrate_v3_to_v4,
+    migrate_v4_to_v5,
+    migrate_v5_to_v6,
+    migrate_v6_to_v7,
+    migrate_v7_to_v8,
+    migrate_v8_to_v9,
+    migrate_v9_to_v10,
+    migrate_v10_to_v11,
+    migrate_v11_to_v12,
+    migrate_v12_to_v13,
+];
+
+pub enum SaveError {
+    Io(std::io::Error),
+    Corrupt(String),
+    TooOld(u16),
+    TooNew(u16),
+    NoFreeSlot,
+}
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "Could not access the save file ({}).", e),
+            SaveError::Corrupt(reason) => write!(f, "The save file is corrupt ({}).", reason),
+            SaveError::TooOld(v) => write!(f, "The save file is too old to load (version {}, oldest supported is {}).", v, oldest_save_version()),
+            SaveError::TooNew(v) => write!(f, "The save file is from a newer version of the game (version {}).", v),
+            SaveError::NoFreeSlot => write!(f, "There are no free save slots left."),
+        }
+    }
+}
+impl From<std::io::Error> for SaveError {
+    fn from(e: std::io::Error) -> Self { SaveError::Io(e) }
+}
+impl From<serde_cbor::Error> for SaveError {
+    fn from(e: serde_cbor::Error) -> Self { SaveError::Corrupt(e.to_string()) }
+}
+
+//Summary info stored alongside the world so the slot picker can describe a save without loading it
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SaveMetadata {
+    pub depth: i32,
+    pub turns: u32,
+    pub health: (i32, i32),
+    pub timestamp: u64,
+    pub permadeath: bool,
+    //Whether the run kept its action log, so its replay carries on when it's loaded
+    pub replayable: bool
+}
+impl SaveMetadata {
+    pub fn from_world(world: &World) -> SaveMetadata {
+        let health = match world.ecs.healths.get(PLAYER) {
+            Some(health) => (health.current, health.max),
+            None => (0, 0)
+        };
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+
+        SaveMetadata {
+            depth: world.depth,
+            turns: world.turns,
+            health,
+            timestamp,
+            permadeath: world.permadeath,
+            replayable: world.action_log.is_some()
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct SaveSlot {
+    pub id: usize,
+    pub meta: Option<SaveMetadata>,
+    pub error: Option<String>
+}
+impl SaveSlot {
+    pub fn is_empty(&self) -> bool { self.meta.is_none() && self.error.is_none() }
+    pub fn is_loadable(&self) -> bool { self.meta.is_some() }
+    pub fn get_name(&self) -> String { format!("Slot {}", self.id + 1) }
+}
+
+#[derive(Serialize)]
+struct SaveFileOut<'a> {
+    meta: SaveMetadata,
+    world: &'a World
+}
+#[derive(Deserialize)]
+struct SaveFileIn {
+    world: World
+}
+//Only the header portion of a save, used when listing slots
+#[derive(Deserialize)]
+struct SaveFileHeader {
+    meta: SaveMetadata
+}
+
+//Where saves went before there were slots; they're headerless version 0 saves
+const LEGACY_SAVE_PATH: &str = "saves/current.save";
+
+fn slot_path(slot: usize) -> String {
+    format!("{}/slot{}.save", SAVE_DIR, slot + 1)
+}
+
+pub fn list_save_slots() -> Vec<SaveSlot> {
+    let mut slots = Vec::new();
+    for id in 0..MAX_SAVE_SLOTS {
+        let mut slot = SaveSlot { id, meta: None, error: None };
+        if slot_exists(id) {
+            match read_slot_file(id).and_then(|v| Ok(serde_cbor::value::from_value::<SaveFileHeader>(v)?)) {
+                Ok(header) => slot.meta = Some(header.meta),
+                Err(e) => slot.error = Some(e.to_string())
+            }
+        }
+        slots.push(slot);
+    }
+    slots
+}
+
+//Moves a save left over from before slots existed into the first free slot, where it gets migrated on load
+//like any other. Returns the slot it went into, or None if there was nothing to move.
+pub fn import_legacy_save() -> Result<Option<usize>, SaveError> {
+    if !Path::new(LEGACY_SAVE_PATH).exists() { return Ok(None) }
+
+    let slot = find_free_slot().ok_or(SaveError::NoFreeSlot)?;
+    rename(LEGACY_SAVE_PATH, slot_path(slot))?;
+    Ok(Some(slot))
+}
+
+pub fn slot_exists(slot: usize) -> bool {
+    Path::new(&slot_path(slot)).exists()
+}
+
+//Returns the first slot with nothing saved in it
+pub fn find_free_slot() -> Option<usize> {
+    list_save_slots().iter().find(|s| s.is_empty()).map(|s| s.id)
+}
+
+//True if at least one slot holds a save that can be loaded
+pub fn any_loadable_saves() -> bool {
+    list_save_slots().iter().any(|s| s.is_loadable())
+}
+
+pub fn export_world(world: &World, slot: usize) -> Result<(), SaveError> {
+    let save = SaveFileOut { meta: SaveMetadata::from_world(world), world };
+    let mut raw_data = Vec::from(&SAVE_MAGIC[..]);
+    raw_data.extend_from_slice(&SAVE_VERSION.to_le_bytes());
+    raw_data.append(&mut serde_cbor::ser::to_vec(&save)?);
+
+    create_dir_all(SAVE_DIR)?;
+    let mut file = File::create(slot_path(slot))?;
+    file.write_all(&raw_data)?;
+    Ok(())
+}
+
+pub fn load_world(slot: usize) -> Result<World, SaveError> {
+    let save: SaveFileIn = serde_cbor::value::from_value(read_slot_file(slot)?)?;
+    Ok(save.world)
+}
+
+pub fn delete_save(slot: usize) -> Result<(), SaveError> {
+    remove_file(slot_path(slot))?;
+    Ok(())
+}
+
+fn read_slot_file(slot: usize) -> Result<Value, SaveError> {
+    let mut file = File::open(slot_path(slot))?;
+    let mut raw_data = Vec::new();
+    file.read_to_end(&mut raw_data)?;
+    read_save_data(&raw_data)
+}
+
+//Checks the header of some raw save data and upgrades the payload to the current version
+fn read_save_data(raw_data: &[u8]) -> Result<Value, SaveError> {
+    let (mut version, mut data) = if raw_data.len() >= HEADER_LEN && raw_data[0..4] == SAVE_MAGIC {
+        let version = u16::from_le_bytes([raw_data[4], raw_data[5]]);
+        (version, serde_cbor::de::from_slice::<Value>(&raw_data[HEADER_LEN..])?)
+    } else {
+        //Saves written before the header existed are raw CBOR
+        let data = serde_cbor::de::from_slice::<Value>(raw_data)?;
+        (detect_headerless_version(&data), data)
+    };
+
+    let oldest = oldest_save_version();
+    if version < oldest { return Err(SaveError::TooOld(version)) }
+    if version > SAVE_VERSION { return Err(SaveError::TooNew(version)) }
+
+    while version < SAVE_VERSION {
+        data = MIGRATIONS[(version - oldest) as usize](data)?;
+        version += 1;
+    }
+    Ok(data)
+}
+
+fn oldest_save_version() -> u16 {
+    SAVE_VERSION - MIGRATIONS.len() as u16
 }
 
-pub fn load_world(gs: &mut State) {
-    let mut file = File::open("saves/current.save").expect("Could not open file!");
-    let deserialized_world: World = serde_cbor::de::from_reader(&file).unwrap();
-    gs.world = deserialized_world;
-}
\ No newline at end of file
+//Headerless saves are either a bare world (version 0) or a world wrapped with slot metadata (version 1)
+fn detect_headerless_version(data: &Value) -> u16 {
+    return if get_field(data, "meta").is_ok() && get_field(data, "world").is_ok() { 1 } else { 0 }
+}
+
+//Helpers for picking apart CBOR maps during migrations
+fn key(name: &str) -> Value { Value::Text(name.to_string()) }
+fn get_field<'a>(data: &'a Value, name: &str) -> Result<&'a Value, SaveError> {
+    match data {
+        Value::Map(map) => map.get(&key(name)).ok_or(SaveError::Corrupt(format!("missing field '{}'", name))),
+        _ => Err(SaveError::Corrupt(format!("expected a map containing '{}'", name)))
+    }
+}
+fn get_map_mut<'a>(data: &'a mut Value) -> Result<&'a mut BTreeMap<Value, Value>, SaveError> {
+    match data {
+        Value::Map(map) => Ok(map),
+        _ => Err(SaveError::Corrupt(String::from("expected a map")))
+    }
+}
+fn get_world_mut(save: &mut Value) -> Result<&mut BTreeMap<Value, Value>, SaveError> {
+    match get_map_mut(save)?.get_mut(&key("world")) {
+        Some(Value::Map(world)) => Ok(world),
+        _ => Err(SaveError::Corrupt(String::from("missing field 'world'")))
+    }
+}
+fn get_int(data: &Value, name: &str) -> Result<i128, SaveError> {
+    match get_field(data, name)? {
+        Value::Integer(i) => Ok(*i),
+        _ => Err(SaveError::Corrupt(format!("field '{}' is not an integer", name)))
+    }
+}
+
+/* MIGRATIONS */
+//v0 -> v1: Wraps the bare world with slot metadata and adds the turn counter
+fn migrate_v0_to_v1(mut world: Value) -> Result<Value, SaveError> {
+    get_map_mut(&mut world)?.insert(key("turns"), Value::Integer(0));
+
+    let depth = get_int(&world, "depth")?;
+    let health = match get_field(&world, "objects")? {
+        Value::Array(objects) if !objects.is_empty() => match get_field(&objects[0], "health") {
+            Ok(health @ Value::Map(_)) => (get_int(health, "current")?, get_int(health, "max")?),
+            _ => (0, 0)
+        },
+        _ => (0, 0)
+    };
+
+    let mut meta = BTreeMap::new();
+    meta.insert(key("depth"), Value::Integer(depth));
+    meta.insert(key("turns"), Value::Integer(0));
+    meta.insert(key("health"), Value::Array(vec![Value::Integer(health.0), Value::Integer(health.1)]));
+    meta.insert(key("timestamp"), Value::Integer(0));
+
+    let mut save = BTreeMap::new();
+    save.insert(key("meta"), Value::Map(meta));
+    save.insert(key("world"), world);
+    Ok(Value::Map(save))
+}
+
+//v1 -> v2: Adds the permadeath flag to the world and its metadata
+fn migrate_v1_to_v2(mut save: Value) -> Result<Value, SaveError> {
+    let map = get_map_mut(&mut save)?;
+    for field in ["meta", "world"].iter() {
+        match map.get_mut(&key(field)) {
+            Some(Value::Map(inner)) => { inner.insert(key("permadeath"), Value::Bool(false)); },
+            _ => return Err(SaveError::Corrupt(format!("missing field '{}'", field)))
+        }
+    }
+    Ok(save)
+}
+
+//v2 -> v3: Adds the run seed; older runs didn't record theirs, so it shows as 0
+fn migrate_v2_to_v3(mut save: Value) -> Result<Value, SaveError> {
+    get_world_mut(&mut save)?.insert(key("seed"), Value::Integer(0));
+    Ok(save)
+}
+
+//v3 -> v4: Moves objects from a plain list into the generational object store, and turns the
+//list indices that objects used to refer to each other with into entity handles
+fn migrate_v3_to_v4(mut save: Value) -> Result<Value, SaveError> {
+    let world = get_world_mut(&mut save)?;
+    let objects = match world.remove(&key("objects")) {
+        Some(Value::Array(objects)) => objects,
+        _ => return Err(SaveError::Corrupt(String::from("missing field 'objects'")))
+    };
+
+    let mut slots = Vec::new();
+    for mut obj in objects.into_iter() {
+        let map = get_map_mut(&mut obj)?;
+        if let Some(Value::Map(inv)) = map.get_mut(&key("in_inventory")) {
+            let owner = inv.remove(&key("owner_id")).unwrap_or(Value::Integer(0));
+            inv.insert(key("owner_id"), index_to_entity(owner)?);
+        }
+        if let Some(Value::Map(ai)) = map.get_mut(&key("ai")) {
+            if let Some(target @ Value::Integer(_)) = ai.remove(&key("target")) {
+                ai.insert(key("target"), index_to_entity(target)?);
+            } else {
+                ai.insert(key("target"), Value::Null);
+            }
+        }
+
+        let mut slot = BTreeMap::new();
+        slot.insert(key("gen"), Value::Integer(0));
+        slot.insert(key("obj"), obj);
+        slots.push(Value::Map(slot));
+    }
+
+    let mut store = BTreeMap::new();
+    store.insert(key("slots"), Value::Array(slots));
+    store.insert(key("free"), Value::Array(Vec::new()));
+    world.insert(key("objects"), Value::Map(store));
+    Ok(save)
+}
+fn index_to_entity(index: Value) -> Result<Value, SaveError> {
+    let mut id = BTreeMap::new();
+    match index {
+        Value::Integer(idx) => id.insert(key("idx"), Value::Integer(idx)),
+        _ => return Err(SaveError::Corrupt(String::from("object reference is not an integer")))
+    };
+    id.insert(key("gen"), Value::Integer(0));
+    Ok(Value::Map(id))
+}
+
+//Which object field each ECS storage is filled from; objects always had a floor and a player memory
+const COMPONENT_FIELDS: &[(&str, &str)] = &[
+    ("name", "names"), ("tag", "tags"), ("pos", "positions"), ("floor", "floors"),
+    ("render", "renders"), ("player_mem", "memories"), ("viewshed", "viewsheds"),
+    ("initiative", "initiatives"), ("in_inventory", "inventories"), ("equip_slot", "equip_slots"),
+    ("health", "healths"), ("damage", "damages"), ("ai", "ais"), ("item_stats", "item_stats"),
+];
+
+//v4 -> v5: Breaks each stored object up into the entity-component storages
+fn migrate_v4_to_v5(mut save: Value) -> Result<Value, SaveError> {
+    let world = get_world_mut(&mut save)?;
+    let store = world.remove(&key("objects")).ok_or(SaveError::Corrupt(String::from("missing field 'objects'")))?;
+    let slots = match get_field(&store, "slots")? {
+        Value::Array(slots) => slots.clone(),
+        _ => return Err(SaveError::Corrupt(String::from("field 'slots' is not a list")))
+    };
+
+    let mut gens = Vec::new();
+    let mut alive = Vec::new();
+    let mut storages: BTreeMap<&str, Vec<Value>> = BTreeMap::new();
+    let mut blockers = Vec::new();
+
+    for slot in slots.iter() {
+        let gen = get_int(slot, "gen")?;
+        gens.push(Value::Integer(gen));
+
+        let obj = match get_field(slot, "obj")? {
+            Value::Map(obj) => Some(obj),
+            _ => None
+        };
+        alive.push(Value::Bool(obj.is_some()));
+
+        for (field, storage) in COMPONENT_FIELDS.iter() {
+            let entry = match obj.and_then(|o| o.get(&key(field))) {
+                Some(Value::Null) | None => Value::Null,
+                Some(component) => Value::Array(vec![Value::Integer(gen), component.clone()])
+            };
+            storages.entry(storage).or_insert_with(Vec::new).push(entry);
+        }
+        blockers.push(match obj.and_then(|o| o.get(&key("block_tile"))) {
+            Some(Value::Bool(true)) => Value::Array(vec![Value::Integer(gen), Value::Null]),
+            _ => Value::Null
+        });
+    }
+
+    let mut ecs = BTreeMap::new();
+    ecs.insert(key("gens"), Value::Array(gens));
+    ecs.insert(key("alive"), Value::Array(alive));
+    ecs.insert(key("free"), get_field(&store, "free")?.clone());
+    for (storage, entries) in storages.into_iter() {
+        let mut map = BTreeMap::new();
+        map.insert(key("entries"), Value::Array(entries));
+        ecs.insert(key(storage), Value::Map(map));
+    }
+    let mut map = BTreeMap::new();
+    map.insert(key("entries"), Value::Array(blockers));
+    ecs.insert(key("blockers"), Value::Map(map));
+
+    world.insert(key("ecs"), Value::Map(ecs));
+    Ok(save)
+}
+
+//v5 -> v6: Adds the storage for turns entities have to sit out; nothing was held up in older saves
+fn migrate_v5_to_v6(mut save: Value) -> Result<Value, SaveError> {
+    add_empty_storage(&mut save, "delays")?;
+    Ok(save)
+}
+//v6 -> v7: Adds the trap storage; older floors had no traps on them
+fn migrate_v6_to_v7(mut save: Value) -> Result<Value, SaveError> {
+    add_empty_storage(&mut save, "traps")?;
+    Ok(save)
+}
+//v7 -> v8: Keeps every floor that was left instead of only the last one. Floors further up than that
+//had their objects cleaned up already, so only the floor just above is worth keeping.
+fn migrate_v7_to_v8(mut save: Value) -> Result<Value, SaveError> {
+    let world = get_world_mut(&mut save)?;
+    let depth = match world.get(&key("depth")) {
+        Some(Value::Integer(depth)) => *depth,
+        _ => return Err(SaveError::Corrupt(String::from("missing field 'depth'")))
+    };
+
+    let mut stored = BTreeMap::new();
+    if let Some(last @ Value::Map(_)) = world.remove(&key("last_map")) {
+        stored.insert(Value::Integer(depth - 1), last);
+    }
+    world.insert(key("stored_maps"), Value::Map(stored));
+    Ok(save)
+}
+//v8 -> v9: Adds side branches. Older runs never had any, so they stay in the main dungeon.
+fn migrate_v8_to_v9(mut save: Value) -> Result<Value, SaveError> {
+    let world = get_world_mut(&mut save)?;
+    world.insert(key("branches"), Value::Array(Vec::new()));
+    world.insert(key("branch"), Value::Integer(0));
+    Ok(save)
+}
+//v9 -> v10: Adds the final depth and the storage for goal items. Older runs get the usual final depth, unless
+//they've already been below it, in which case the run ends on the floor past the deepest one they've seen.
+fn migrate_v9_to_v10(mut save: Value) -> Result<Value, SaveError> {
+    add_empty_storage(&mut save, "goals")?;
+
+    let world = get_world_mut(&mut save)?;
+    let mut deepest = match (world.get(&key("branch")), world.get(&key("depth"))) {
+        (Some(Value::Integer(0)), Some(Value::Integer(depth))) => *depth,
+        (Some(_), Some(_)) => 0,
+        _ => return Err(SaveError::Corrupt(String::from("missing field 'depth'")))
+    };
+    if let Some(Value::Map(stored)) = world.get(&key("stored_maps")) {
+        for floor in stored.keys() {
+            if let Value::Integer(k) = floor {
+                if split_floor_key(*k as i32).0 == MAIN_DUNGEON { deepest = deepest.max(*k) }
+            }
+        }
+    }
+    world.insert(key("final_depth"), Value::Integer(deepest.max(DEFAULT_FINAL_DEPTH as i128 - 1) + 1));
+    Ok(save)
+}
+//v10 -> v11: Adds the light source storage; floors built before lighting existed have no torches on them
+fn migrate_v10_to_v11(mut save: Value) -> Result<Value, SaveError> {
+    add_empty_storage(&mut save, "lights")?;
+    Ok(save)
+}
+//v11 -> v12: Keeps the boss and goal item on the world. Older runs looked them up when the final floor was built,
+//so they get whatever the content database has now, just as they would have before.
+fn migrate_v11_to_v12(mut save: Value) -> Result<Value, SaveError> {
+    let endgame = Endgame::load();
+    let world = get_world_mut(&mut save)?;
+    world.insert(key("boss_id"), Value::Integer(endgame.boss_id as i128));
+    world.insert(key("goal_item_id"), Value::Integer(endgame.goal_item_id as i128));
+    Ok(save)
+}
+//v12 -> v13: Adds the run's action log. Older runs didn't keep theirs, so they're marked as having no replay.
+fn migrate_v12_to_v13(mut save: Value) -> Result<Value, SaveError> {
+    get_world_mut(&mut save)?.insert(key("action_log"), Value::Null);
+    match get_map_mut(&mut save)?.get_mut(&key("meta")) {
+        Some(Value::Map(meta)) => { meta.insert(key("replayable"), Value::Bool(false)); },
+        _ => return Err(SaveError::Corrupt(String::from("missing field 'meta'")))
+    }
+    Ok(save)
+}
+//Inserts an ECS storage that no entity has an entry in yet
+fn add_empty_storage(save: &mut Value, storage: &str) -> Result<(), SaveError> {
+    let ecs = match get_world_mut(save)?.get_mut(&key("ecs")) {
+        Some(ecs) => get_map_mut(ecs)?,
+        None => return Err(SaveError::Corrupt(String::from("missing field 'ecs'")))
+    };
+    let mut map = BTreeMap::new();
+    map.insert(key("entries"), Value::Array(Vec::new()));
+    ecs.insert(key(storage), Value::Map(map));
+    Ok(())
+}
+
+//Formats a unix timestamp as a UTC date and time, e.g. "2021-06-12 14:05"
+pub fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86400) as i64;
+    let secs = timestamp % 86400;
+
+    //Converts days since the epoch into a civil calendar date
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02} {:02}:{:02}", year, month, day, secs / 3600, (secs % 3600) / 60)
+}
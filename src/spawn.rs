@@ -42,15 +42,63 @@ pub fn make_corpse(pos: Point, floor: i32) -> Object {
     }
 }
 
+pub fn make_trap(kind: TrapKind) -> Object {
+    let (name, color) = match kind {
+        TrapKind::Dart => ("Dart Trap", LIGHTGRAY),
+        TrapKind::Teleport => ("Teleport Trap", MAGENTA),
+        TrapKind::Alarm => ("Alarm Trap", YELLOW),
+        TrapKind::Trapdoor => ("Trapdoor", CHOCOLATE)
+    };
+    Object {
+        name: Some(name.to_string()),
+        tag: Some(ActorTag::Static),
+        render: Some(Render {
+            glyph: 94,
+            color: ColorPair::new(color, BLACK),
+            order: 2
+        }),
+        trap: Some(Trap::new(kind)),
+
+        ..Object::blank()
+    }
+}
+
+//A torch mounted on a wall, lighting up the area around it
+pub fn make_torch() -> Object {
+    Object {
+        name: Some("Torch".to_string()),
+        tag: Some(ActorTag::Static),
+        render: Some(Render {
+            glyph: 231,
+            color: ColorPair::new(ORANGE, BLACK),
+            order: 2
+        }),
+        light: Some(LightSource { radius: 6, color: RGBA::from_u8(255, 170, 80, 255) }),
+
+        ..Object::blank()
+    }
+}
+
+//Traps get more common the deeper you go; the first floor is spared trapdoors
+pub fn get_trap_spawns(depth: i32, rng: &mut RandomNumberGenerator) -> Vec<Object> {
+    let count = rng.range(0, 2) + depth / 2 + 1;
+    let kinds = [TrapKind::Dart, TrapKind::Teleport, TrapKind::Alarm, TrapKind::Trapdoor];
 
-pub fn try_find_spawnable_position(room: &Rect, blocked_points: &Vec<Point>, non_blocking_object: bool, rng: &mut RandomNumberGenerator) -> Option<Point> {
-    let points = room.point_set().iter().map(|p| *p).collect::<Vec<Point>>();
+    return (0..count).map(|_| {
+        let kind = kinds[rng.range(0, kinds.len())];
+        make_trap(if kind == TrapKind::Trapdoor && depth < 2 { TrapKind::Dart } else { kind })
+    }).collect()
+}
+
+pub fn try_find_spawnable_position(region: &SpawnRegion, blocked_points: &Vec<Point>, non_blocking_object: bool, rng: &mut RandomNumberGenerator) -> Option<Point> {
+    //Regions keep their points in order rather than in a hashed set, so seeded spawns always come out the same
+    let points = &region.points;
     let unblocked_points = { let mut v = points.to_vec(); v.retain(|p| !blocked_points.contains(p)); v };
 
     //If all of the tiles are blocked and the object is non-blocking, use any point.
     let point_array = match unblocked_points.len() > 0 {
         true    =>  { &unblocked_points }
-        false   =>  { if !non_blocking_object { return None } else { &points } }
+        false   =>  { if !non_blocking_object { return None } else { points } }
     };
 
     let random_point = rng.range(0, point_array.len());
@@ -62,17 +110,11 @@ pub fn add_positional_info(init_obj: &mut Object, pos: Point, depth: i32) {
     init_obj.floor = depth;
 }
 
-pub fn get_enemy_spawn_table(depth: i32, num_enemies: i32, rng: &mut RandomNumberGenerator) -> Vec<Object> {
+pub fn get_enemy_spawn_table(table_id: u32, num_enemies: i32, rng: &mut RandomNumberGenerator) -> Vec<Object> {
     let mut enemies: Vec<Object> = Vec::new();
 
     let conn = open_connection();
 
-    //Some BS static table IDs correlated to depth
-    let table_id = match depth {
-        1|2|3 => 1,
-        _ => 2
-    };
-
     //Builds a list of enemy IDs to spawn
     let id_list= {
         let mut ids = Vec::new();
@@ -120,8 +162,8 @@ pub fn get_enemy_spawn_table(depth: i32, num_enemies: i32, rng: &mut RandomNumbe
     return enemies
 }
 
-pub fn get_item_spawns(depth: i32, rng: &mut RandomNumberGenerator) -> Vec<Object> {
-    //Only test code for now. Just grabs a vec of a bunch of potions.
+pub fn get_item_spawns(budget: i32, rng: &mut RandomNumberGenerator) -> Vec<Object> {
+    //Only test code for now. Just grabs a vec of as many potions as the floor's budget allows.
     let conn = open_connection();
     let mut items = import_items_to_objects(&conn,
                                             String::from("V_ItemsFull"),
@@ -129,7 +171,8 @@ pub fn get_item_spawns(depth: i32, rng: &mut RandomNumberGenerator) -> Vec<Objec
     ).expect("Failed to import starting items from the database.");
     conn.close().expect("Connection to SQLite DB failed to close.");
 
-    for _ in 1..=4 { items.push(items[0].clone()); }
+    if budget < 1 { return Vec::new() }
+    for _ in 2..=budget { items.push(items[0].clone()); }
 
     items
 }
@@ -143,12 +186,29 @@ pub fn get_starting_equip() -> Vec<Object> {
     conn.close().expect("Connection to SQLite DB failed to close.");
 
     for obj in items.iter_mut() {
-        obj.in_inventory = Some(InInventory{ owner_id: 0 })
+        obj.in_inventory = Some(InInventory{ owner_id: PLAYER })
     }
     return items
 }
 
-pub fn give_items(objects: &mut Vec<Object>, obj_id: usize, item_ids: Vec<i32>) {
+//Grabs the boss of the final floor and the goal item it guards
+pub fn get_final_encounter(endgame: &Endgame) -> (Object, Object) {
+    let conn = open_connection();
+    let boss = import_enemies_to_objects(&conn,
+                                         String::from("V_EnemiesFull"),
+                                         Some(format!("id = {}", endgame.boss_id))
+    ).expect("Failed to import the boss from the database.").remove(0);
+    let mut goal = import_items_to_objects(&conn,
+                                           String::from("V_ItemsFull"),
+                                           Some(format!("id = {}", endgame.goal_item_id))
+    ).expect("Failed to import the goal item from the database.").remove(0);
+    conn.close().expect("Connection to SQLite DB failed to close.");
+
+    goal.goal = true;
+    return (boss, goal)
+}
+
+pub fn give_items(ecs: &mut Ecs, obj_id: EntityId, item_ids: Vec<i32>) {
     let ids_in = {
         let mut base_string = String::new();
         for id in item_ids.iter() {
@@ -166,5 +226,5 @@ pub fn give_items(objects: &mut Vec<Object>, obj_id: usize, item_ids: Vec<i32>)
     conn.close().expect("Connection to SQLite DB failed to close.");
 
     for obj in items.iter_mut() { obj.in_inventory = Some(InInventory{ owner_id: obj_id }); }
-    for obj in items.into_iter() { objects.push(obj) }
+    for obj in items.into_iter() { ecs.spawn(obj); }
 }
\ No newline at end of file
@@ -1,11 +1,7 @@
 use crate::prelude::*;
-use serde::{Serialize, Deserialize};
 
-#[derive(PartialEq)]
-pub enum TurnState { Player, AI, GameOver }
-
-#[derive(PartialEq)]
-pub enum ContextStatus{ InGame, InventoryOpen, MainMenu, PauseMenu }
+#[derive(Clone, Copy, PartialEq)]
+pub enum ContextStatus{ InGame, InventoryOpen, MainMenu, PauseMenu, SlotMenu, SeedEntry, Victory }
 
 pub struct MouseLocation {
     current: Point,
@@ -26,65 +22,137 @@ impl MouseLocation {
 }
 
 pub struct State {
-    pub world: World,
-    pub turn_state: TurnState,
+    pub game: Game,
     pub menu: Option<Menu>,
     pub inv: Option<InventoryMenu>,
-    pub proc: bool,
-    pub passed: bool,
-    pub gameover: bool,
     pub exit: bool,
     pub con_status: ContextStatus,
+    pub slot_menu_return: ContextStatus,
+    pub permadeath: bool,
+    pub seed_input: String,
+    pub replay: Option<ReplayPlayer>,
     pub refresh_con: bool,
-    pub logs: LogBuffer,
     pub mouse_pos: MouseLocation
 }
 impl State {
     pub fn init() -> State {
+        //Done before the menu is built, so an old save counts towards whether there's anything to load
+        let imported = import_legacy_save();
+        let mut menu = Menu::main_menu(false);
+        menu.message = match imported {
+            Ok(Some(slot)) => Some(format!("Your old save was moved to Slot {}.", slot + 1)),
+            Ok(None) => None,
+            Err(e) => Some(format!("Your old save could not be moved to a slot. {}", e))
+        };
+
         State {
-            world: World::empty(),
-            turn_state: TurnState::Player,
-            menu: Some(Menu::main_menu()),
+            game: Game::empty(),
+            menu: Some(menu),
             inv: None,
-            proc: true,
-            passed: false,
-            gameover: false,
             exit: false,
             con_status: ContextStatus::MainMenu,
+            slot_menu_return: ContextStatus::MainMenu,
+            permadeath: false,
+            seed_input: String::new(),
+            replay: None,
             refresh_con: true,
-            logs: LogBuffer::new(),
             mouse_pos: MouseLocation::new()
         }
     }
     fn handle_menu_actions(&mut self) {
-        if let Some(selection) = self.menu.as_ref().unwrap().processed_selection {
+        if let Some(selection) = self.menu.as_mut().unwrap().processed_selection.take() {
             match selection {
                 MenuSelection::NewGame => {
-                    self.world = World::new_game();
-                    self.logs.clear();
-                    self.con_status = ContextStatus::InGame;
+                    self.start_new_game(RandomNumberGenerator::new().next_u64(), self.permadeath);
+                },
+                MenuSelection::NewGameSeeded => {
+                    self.seed_input.clear();
+                    self.con_status = ContextStatus::SeedEntry;
                     self.refresh_con = true;
-                    self.proc = true;
-
-                    self.logs.update_logs(LogMessage::new()
-                        .add_part("Your adventure begins now. ", ColorPair::new(WHITE,GREY10))
-                        .add_part("Prepare to die...", ColorPair::new(RED, GREY10))
-                    );
+                },
+                MenuSelection::WatchReplay => {
+                    match ReplayPlayer::load() {
+                        Ok(player) => {
+                            let (seed, permadeath) = (player.seed, player.permadeath);
+                            self.replay = Some(player);
+                            self.start_new_game(seed, permadeath);
+                        },
+                        Err(e) => {
+                            self.menu.as_mut().unwrap().message = Some(e);
+                            self.refresh_con = true;
+                        }
+                    }
                 },
                 MenuSelection::SaveGame => {
-                    export_world(&self.world);
-                    self.con_status = ContextStatus::InGame;
-                    self.refresh_con = true;
+                    self.open_slot_menu(SlotMenuMode::Save);
                 },
                 MenuSelection::LoadGame => {
-                    load_world(self);
-                    self.logs.clear();
-                    self.con_status = ContextStatus::InGame;
+                    self.open_slot_menu(SlotMenuMode::Load);
+                },
+                MenuSelection::SaveSlot(slot) => {
+                    match export_world(&self.game.world, slot) {
+                        Ok(_) => {
+                            self.con_status = ContextStatus::InGame;
+                            self.game.logs.update_logs(LogMessage::new()
+                                .add_part(format!("Game saved to slot {}.", slot + 1), ColorPair::new(WHITE, GREY10))
+                            );
+                        },
+                        Err(e) => self.menu.as_mut().unwrap().message = Some(e.to_string())
+                    }
+                    self.refresh_con = true;
+                },
+                MenuSelection::LoadSlot(slot) => {
+                    if self.menu.as_ref().unwrap().slots[slot].is_empty() { return }
+
+                    match load_world(slot) {
+                        Ok(world) => {
+                            //Permadeath saves are consumed on load so a death can't be undone by reloading
+                            if world.permadeath {
+                                if let Err(e) = delete_save(slot) { console::log(format!("Could not consume permadeath save: {}", e)) }
+                            }
+                            self.game = Game::from_world(world);
+                            self.game.world.save_slot = Some(slot);
+                            self.resume_recording();
+                            self.con_status = ContextStatus::InGame;
+                        },
+                        Err(e) => self.menu.as_mut().unwrap().message = Some(e.to_string())
+                    }
+                    self.refresh_con = true;
+                },
+                MenuSelection::DeleteSlot(slot) => {
+                    let menu = self.menu.as_mut().unwrap();
+                    menu.message = delete_save(slot).err().map(|e| e.to_string());
+                    menu.refresh_slots();
+                    self.refresh_con = true;
+                },
+                MenuSelection::Back => {
+                    self.menu = Some(match self.slot_menu_return {
+                        ContextStatus::PauseMenu => Menu::pause_menu(self.game.world.permadeath),
+                        _ => Menu::main_menu(self.permadeath)
+                    });
+                    self.con_status = self.slot_menu_return;
                     self.refresh_con = true;
                 },
                 MenuSelection::Quit => {
+                    //Quitting a permadeath run saves it so it can be resumed later
+                    if self.con_status == ContextStatus::PauseMenu && self.game.world.permadeath {
+                        let result = match self.game.world.save_slot.or_else(find_free_slot) {
+                            Some(slot) => export_world(&self.game.world, slot),
+                            None => Err(SaveError::NoFreeSlot)
+                        };
+                        if let Err(e) = result {
+                            self.menu.as_mut().unwrap().message = Some(e.to_string());
+                            self.refresh_con = true;
+                            return
+                        }
+                    }
                     self.exit = true
                 },
+                MenuSelection::TogglePermadeath => {
+                    self.permadeath = !self.permadeath;
+                    self.menu.as_mut().unwrap().permadeath = self.permadeath;
+                    self.refresh_con = true;
+                },
                 MenuSelection::Continue => {
                     self.con_status = ContextStatus::InGame;
                     self.refresh_con = true;
@@ -92,12 +160,71 @@ impl State {
             }
         }
     }
+    //Carries on recording a loaded run's replay from where it was saved, saying so in the logs if it can't
+    fn resume_recording(&mut self) {
+        let world = &self.game.world;
+        let result = match &world.action_log {
+            Some(log) => ReplayRecorder::resume(world.seed, world.permadeath, log),
+            None => Err(String::from("This run was saved before replays were kept."))
+        };
+        match result {
+            Ok(recorder) => self.game.recorder = Some(recorder),
+            Err(e) => self.game.logs.update_logs(LogMessage::new()
+                .add_part(format!("{} It won't be recorded.", e), ColorPair::new(GREY65, GREY10))
+            )
+        }
+    }
+    pub fn start_new_game(&mut self, seed: u64, permadeath: bool) {
+        self.game = Game::new(seed, permadeath);
+        //Runs started to watch a replay must not overwrite the file being watched
+        if self.replay.is_none() {
+            self.game.recorder = match ReplayRecorder::start(seed, permadeath) {
+                Ok(recorder) => Some(recorder),
+                Err(e) => {
+                    console::log(e);
+                    None
+                }
+            };
+        }
+        self.con_status = ContextStatus::InGame;
+        self.refresh_con = true;
+    }
+    //Runs the game's systems, and tells the player how to get out once the game has ended
+    pub fn exec_all_systems(&mut self) {
+        let was_over = self.game.turn_state == TurnState::GameOver;
+        let had_won = self.game.turn_state == TurnState::Victory;
+        self.game.exec_all_systems();
+
+        //A won run goes straight to the victory screen, unless it's only a replay of one
+        if !had_won && self.game.turn_state == TurnState::Victory && self.replay.is_none() {
+            self.inv = None;
+            self.con_status = ContextStatus::Victory;
+            self.refresh_con = true;
+        }
+
+        if !was_over && self.game.turn_state == TurnState::GameOver && self.replay.is_none() {
+            self.game.logs.update_logs(LogMessage::new()
+                .add_part("Press", ColorPair::new(WHITE, GREY10))
+                .add_part("Enter", ColorPair::new(LIME_GREEN, GREY10))
+                .add_part("or", ColorPair::new(WHITE, GREY10))
+                .add_part("R", ColorPair::new(LIME_GREEN, GREY10))
+                .add_part("to return to the main menu.", ColorPair::new(WHITE, GREY10))
+            );
+        }
+    }
+    fn open_slot_menu(&mut self, mode: SlotMenuMode) {
+        self.slot_menu_return = self.con_status;
+        self.menu = Some(Menu::slot_menu(mode));
+        self.con_status = ContextStatus::SlotMenu;
+        self.refresh_con = true;
+    }
 }
 impl GameState for State {
     fn tick(&mut self, con: &mut BTerm) {
-        //Only take player input if it's the player's turn
-        if self.turn_state == TurnState::Player { player_input(self, con) }
-        else if self.turn_state == TurnState::GameOver { game_over_input(self, con) }
+        //Only take player input if it's the player's turn; a replay takes over the controls while it plays
+        if self.replay.is_some() { replay_input(self, con) }
+        else if self.game.turn_state == TurnState::Player { player_input(self, con) }
+        else if self.game.turn_state == TurnState::GameOver || self.game.turn_state == TurnState::Victory { game_over_input(self, con) }
 
         self.mouse_pos.get_pos(con);
         if self.mouse_pos.has_changed() { self.refresh_con = true }
@@ -105,20 +232,24 @@ impl GameState for State {
         match self.con_status {
             //If the game is in it's normal running state
             ContextStatus::InGame | ContextStatus::InventoryOpen => {
+                //Feed the next recorded actions in if a replay is playing
+                if self.replay.is_some() { advance_replay(self, con.frame_time_ms) }
+
                 //Run all systems
-                exec_all_systems(self);
+                self.exec_all_systems();
 
                 //Redraw to the console if it needs to be refreshed
                 if self.refresh_con {
                     con.cls();
-                    batch_all(&self.world.active_map, &self.world.camera, &self.world.objects, &self.logs, self.world.depth, self.mouse_pos.current);
-                    if self.con_status == ContextStatus::InventoryOpen { batch_inventory_menu(self.inv.as_mut().unwrap(), &self.world.objects); }
+                    batch_all(&self.game.world, &self.game.logs, self.mouse_pos.current);
+                    if self.con_status == ContextStatus::InventoryOpen { batch_inventory_menu(self.inv.as_mut().unwrap(), &self.game.world.ecs); }
+                    if let Some(replay) = &self.replay { batch_replay_status(replay); }
                     render_draw_buffer(con).expect("Error rendering draw buffer to the console!");
                     self.refresh_con = false;
                 }
             },
             //If the game is in a menu of some sort
-            ContextStatus::MainMenu | ContextStatus::PauseMenu => {
+            ContextStatus::MainMenu | ContextStatus::PauseMenu | ContextStatus::SlotMenu => {
                 //Redraw if necessary
                 if self.refresh_con {
                     con.cls();
@@ -126,6 +257,7 @@ impl GameState for State {
                     match self.con_status {
                         ContextStatus::MainMenu => batch_main_menu(self.menu.as_ref().unwrap()),
                         ContextStatus::PauseMenu => batch_pause_menu(self.menu.as_ref().unwrap()),
+                        ContextStatus::SlotMenu => batch_slot_menu(self.menu.as_ref().unwrap()),
                         _ => {}
                     }
                     render_draw_buffer(con).expect("Error rendering draw buffer to the console!");
@@ -134,147 +266,25 @@ impl GameState for State {
                 //If any menu actions are ready to run, run them
                 self.handle_menu_actions();
             },
+            ContextStatus::SeedEntry => {
+                if self.refresh_con {
+                    con.cls();
+                    batch_seed_entry(&self.seed_input);
+                    render_draw_buffer(con).expect("Error rendering draw buffer to the console!");
+                    self.refresh_con = false;
+                }
+            },
+            ContextStatus::Victory => {
+                if self.refresh_con {
+                    con.cls();
+                    batch_victory_screen(&RunSummary::from_world(&self.game.world));
+                    render_draw_buffer(con).expect("Error rendering draw buffer to the console!");
+                    self.refresh_con = false;
+                }
+            },
         }
 
         //Close the game if the player chooses to exit
         if self.exit == true {con.quit()}
     }
 }
-
-#[derive(Serialize,Deserialize)]
-pub struct World {
-    pub rng: RandomNumberGenerator,
-    pub objects: Vec<Object>,
-    pub active_map: Map,
-    pub last_map: Option<Map>,
-    pub depth: i32,
-    pub camera: Camera,
-}
-impl World {
-    pub fn empty() -> World {
-        World {
-            rng: RandomNumberGenerator::new(),
-            objects: Vec::new(),
-            active_map: Map::new(0,0),
-            last_map: None,
-            depth: 0,
-            camera: Camera::new(Point::zero()),
-        }
-    }
-    pub fn new_game() -> World {
-        let mut rng = RandomNumberGenerator::new();
-        let mapgen = MapGenerator::generate(GenerationMode::RandomRooms, 60, 60, 1, &mut rng);
-
-        let startpos = mapgen.rooms[0].center();
-
-        let mut world = World {
-            rng,
-            objects: Vec::new(),
-            active_map: mapgen.map,
-            last_map: None,
-            depth: 1,
-            camera: Camera::new(startpos),
-        };
-
-        //Spawn the player object
-        let player = spawn_player(startpos);
-        world.objects.insert(0, player);
-
-        //Spawn starting equipment in the player's inventory
-        let start_equip: Vec<Object> = get_starting_equip();
-        for item in start_equip.into_iter() {
-            world.objects.push(item);
-        }
-
-        //Place all objects generated alongside the map into the game world
-        for obj in mapgen.objects.into_iter() {
-            world.objects.push(obj)
-        }
-
-        return world;
-    }
-    pub fn descend_to_next(&mut self) {
-        //Copy the old map to the last_map member
-        self.last_map = Some(Map::from_copy(&self.active_map));
-        self.depth += 1;
-        self.objects[0].floor = self.depth;
-
-        //Set up a new map
-        let mapgen = MapGenerator::generate(GenerationMode::RandomRooms, 60, 60, self.depth, &mut self.rng);
-        self.objects[0].pos = Some(mapgen.rooms[0].center());
-        self.objects[0].viewshed.as_mut().unwrap().refresh = true;
-        self.camera = Camera::new(mapgen.rooms[0].center());
-        self.active_map = mapgen.map;
-
-        for obj in mapgen.objects.into_iter() {
-            self.objects.push(obj)
-        }
-
-        self.clean_garbage_objects();
-    }
-
-    fn clean_garbage_objects(&mut self) {
-        //Clean up any objects that are 2 floors above, but are not in any inventory
-        let mut removelist: Vec<usize> = Vec::new();
-        for (i, obj) in self.objects.iter().enumerate() {
-            if obj.floor < self.depth - 1 && obj.in_inventory.is_none() {
-                removelist.push(i);
-            }
-        }
-        //Second pass for item inventory ownership; delete items on things that are to be deleted
-        for (i, obj) in self.objects.iter().enumerate() {
-            if let Some(inv) = &obj.in_inventory {
-                if removelist.contains(&inv.owner_id) {
-                    removelist.push(i);
-                }
-            }
-        }
-        for i in removelist.iter() {
-            self.objects.remove(*i);
-        }
-    }
-}
-
-fn exec_all_systems(gs: &mut State) {
-    if gs.proc {
-        process_fov(&mut gs.world.objects, &mut gs.world.active_map);
-        update_blocked_tiles(&gs.world.objects, &mut gs.world.active_map, gs.world.depth);
-        proc_all_wounds(&mut gs.world.objects, &mut gs.logs, &mut gs.gameover);
-
-        //Check if the player's turn was passed
-        if gs.passed {
-            gs.turn_state = TurnState::AI;
-            gs.passed = false;
-            process_fov(&mut gs.world.objects, &mut gs.world.active_map);
-            proc_regen(&mut gs.world.objects);
-        }
-
-        //Run any stuff for the AI if it's the AI's turn
-        if gs.turn_state == TurnState::AI {
-            process_ai(&mut gs.world.objects, &mut gs.world.active_map, gs.world.depth, &mut gs.world.rng);
-            process_fov(&mut gs.world.objects, &mut gs.world.active_map);
-            proc_all_wounds(&mut gs.world.objects, &mut gs.logs, &mut gs.gameover);
-            gs.turn_state = TurnState::Player;
-        }
-
-        update_player_memory(&mut gs.world.objects);
-
-        //Set the turn state on a game over event.
-        if gs.gameover {
-            gs.logs.update_logs(LogMessage::new()
-                .add_part("Press", ColorPair::new(WHITE, GREY10))
-                .add_part("Enter", ColorPair::new(LIME_GREEN, GREY10))
-                .add_part("or", ColorPair::new(WHITE, GREY10))
-                .add_part("R", ColorPair::new(LIME_GREEN, GREY10))
-                .add_part("to return to the main menu.", ColorPair::new(WHITE, GREY10))
-            );
-            gs.logs.update_logs(LogMessage::new()
-                .add_part(format!("You have perished on level {}.", gs.world.depth), ColorPair::new(BLACK, RED))
-            );
-            gs.turn_state = TurnState::GameOver;
-            gs.gameover = false;
-        }
-
-        gs.proc = false;
-    }
-}
@@ -1,72 +1,86 @@
 use crate::prelude::*;
 
-pub fn process_ai(objects: &mut Vec<Object>, map: &mut Map, floor: i32, rng: &mut RandomNumberGenerator) {
-    let (player, all) = objects.split_at_mut(1);
-    let player_pos = player[0].pos.unwrap();
+pub fn process_ai(ecs: &mut Ecs, map: &mut Map, floor: i32, rng: &mut RandomNumberGenerator) {
+    let player_pos = *ecs.positions.get(PLAYER).unwrap();
     let mut proclist: InitList = InitList::new();
 
-    for (id, obj) in all.iter().enumerate() {
-        if let Object{ tag: Some(tag), initiative: Some(init), .. } = obj {
-            if *tag == ActorTag::Enemy {
-                proclist.add_object(id, *init);
-            }
+    for (id, tag, init) in ecs.tags.join(&ecs.initiatives) {
+        if *tag == ActorTag::Enemy {
+            proclist.add_object(id, *init);
         }
     }
     proclist.sort();
 
     for unit in proclist.iter() {
-        basic_enemy_ai(unit.0, objects, map, rng, player_pos);
-        update_blocked_tiles(objects, map, floor);
+        basic_enemy_ai(unit.0, ecs, map, rng, player_pos);
+        update_blocked_tiles(ecs, map, floor);
     }
 }
 
-fn basic_enemy_ai(enemy_id: usize, objects: &mut Vec<Object>, map: &Map, rng: &mut RandomNumberGenerator, player_pos: Point) {
-    let (player, all) = &mut objects.split_at_mut(1);
-    let enemy = &mut all[enemy_id];
-    let player = &mut player[0];
-    let pos = enemy.pos.unwrap();
+fn basic_enemy_ai(enemy_id: EntityId, ecs: &mut Ecs, map: &mut Map, rng: &mut RandomNumberGenerator, player_pos: Point) {
+    //Anything killed earlier in the turn is skipped
+    let pos = match ecs.positions.get(enemy_id) {
+        Some(pos) => *pos,
+        None => return
+    };
+
+    //Anything held up by the terrain sits this turn out
+    if ecs.take_delay(enemy_id) { return }
+
+    if ecs.get_floor(enemy_id) == ecs.get_floor(PLAYER) {
+        //The player can only be picked out of the dark from close by
+        let can_see_player = match ecs.viewsheds.get(enemy_id) {
+            Some(view) => can_spot(map, pos, view, player_pos),
+            None => return
+        };
+        let ai = match ecs.ais.get_mut(enemy_id) {
+            Some(ai) => ai,
+            None => return
+        };
 
-    if enemy.floor == player.floor {
-        if let Object { viewshed: Some(view), ai: Some(ai), .. } = enemy {
-            if view.visible.contains(&player_pos) && enemy.floor == player.floor {
-                ai.target = Some(0);
-                ai.state = AIState::Chasing;
-                ai.tgt_memory = 24;
-                ai.tgt_heatmap.reset_to_single_node(&player_pos, 5);
+        if can_see_player {
+            ai.target = Some(PLAYER);
+            ai.state = AIState::Chasing;
+            ai.tgt_memory = 24;
+            ai.tgt_heatmap.reset_to_single_node(&player_pos, 5);
 
-                let mut dest: Point = pos;
-                let distance = DistanceAlg::Pythagoras.distance2d(pos, player_pos);
-                let targets = vec![map.index(player_pos.x, player_pos.y)];
-                let dijkstra_map = DijkstraMap::new(90, 90, &targets, map, 1024.0);
+            let mut dest: Point = pos;
+            let distance = DistanceAlg::Pythagoras.distance2d(pos, player_pos);
+            let targets = vec![map.index(player_pos.x, player_pos.y)];
+            let dijkstra_map = DijkstraMap::new(map.width, map.height, &targets, map, 1024.0);
 
-                if let Some(destidx) = DijkstraMap::find_lowest_exit(&dijkstra_map, map.index(pos.x, pos.y), map) {
-                    dest = if distance > 1.45 {
-                        map.point_from_idx(destidx)
-                    } else {
-                        player_pos
-                    };
-                }
-                if distance <= 1.45 {
-                    enemy.try_attack(player, rng);
-                } else if dest != pos { enemy.try_move(dest, map) }
-            } else if ai.tgt_memory > 0 {
-                ai.state = AIState::Hunting;
-                ai.tgt_memory -= 1;
-                ai.tgt_heatmap.spread(pos, map);
-                let dest = ai.tgt_heatmap.get_closest_heat(map, pos);
-                if dest != pos { enemy.try_move(dest, map) }
-            } else {
-                ai.target = None;
-                ai.state = AIState::Idle;
-                if let Object { health: Some(health), .. } = enemy { health.set_regen_valid(true); }
+            if let Some(destidx) = DijkstraMap::find_lowest_exit(&dijkstra_map, map.index(pos.x, pos.y), map) {
+                dest = if distance > 1.45 {
+                    map.point_from_idx(destidx)
+                } else {
+                    player_pos
+                };
             }
+            if distance <= 1.45 {
+                try_attack(ecs, enemy_id, PLAYER, rng);
+            } else if dest != pos { step_to(ecs, enemy_id, dest, map) }
+        } else if ai.tgt_memory > 0 {
+            ai.state = AIState::Hunting;
+            ai.tgt_memory -= 1;
+            ai.tgt_heatmap.spread(pos, map);
+            let dest = ai.tgt_heatmap.get_closest_heat(map, pos);
+            if dest != pos { step_to(ecs, enemy_id, dest, map) }
+        } else {
+            ai.target = None;
+            ai.state = AIState::Idle;
+            if let Some(health) = ecs.healths.get_mut(enemy_id) { health.set_regen_valid(true); }
         }
-        clear_ai_heatmap(enemy);
+        clear_ai_heatmap(ecs, enemy_id);
     }
 }
 
-fn clear_ai_heatmap(enemy: &mut Object) {
-    if let Object { viewshed: Some(view), ai: Some(ai), ..} = enemy {
+//Moves onto the next tile of a path, opening the door there first if there is one
+fn step_to(ecs: &mut Ecs, enemy_id: EntityId, dest: Point, map: &mut Map) {
+    if !try_open_door(ecs, enemy_id, dest, map) { try_move(ecs, enemy_id, dest, map); }
+}
+
+fn clear_ai_heatmap(ecs: &mut Ecs, enemy_id: EntityId) {
+    if let (Some(view), Some(ai)) = (ecs.viewsheds.get(enemy_id), ecs.ais.get_mut(enemy_id)) {
         ai.tgt_heatmap.clear_heat_area(&view.visible);
     }
-}
\ No newline at end of file
+}
@@ -1,10 +1,10 @@
 use crate::prelude::*;
 
-pub fn drink_object(objects: &mut Vec<Object>, source: usize, logs: &mut LogBuffer, rng: &mut RandomNumberGenerator) {
-    let owner = objects[source].in_inventory.as_ref().unwrap().owner_id.clone();
-    let name = objects[source].name.as_ref().unwrap_or(&format!("NIL")).clone();
-    let color = objects[source].render.as_ref().unwrap_or(&Render::nil_render()).clone().color.fg;
-    let effects = objects[source].item_stats.as_ref().unwrap_or(&ItemStats::blank_with_drop()).effects.clone();
+pub fn drink_object(ecs: &mut Ecs, source: EntityId, logs: &mut LogBuffer, rng: &mut RandomNumberGenerator) {
+    let owner = ecs.inventories.get(source).unwrap().owner_id;
+    let name = ecs.get_name(source);
+    let color = ecs.get_fg(source);
+    let effects = ecs.item_stats.get(source).unwrap_or(&ItemStats::blank_with_drop()).effects.clone();
 
     for effect in effects.into_iter() {
         match effect.etype {
@@ -15,7 +15,7 @@ pub fn drink_object(objects: &mut Vec<Object>, source: usize, logs: &mut LogBuff
                 };
                 let dice_roll = rng.roll_dice(n, die_type);
 
-                if let Some(owner_health) = &mut objects[owner].health {
+                if let Some(owner_health) = ecs.healths.get_mut(owner) {
                     let amt_healed = owner_health.heal(dice_roll);
                     logs.update_logs(LogMessage::new()
                         .add_part("You drink the", ColorPair::new(WHITE,GREY10))
@@ -26,7 +26,7 @@ pub fn drink_object(objects: &mut Vec<Object>, source: usize, logs: &mut LogBuff
                     );
                 }
 
-                objects[source].item_stats.as_mut().unwrap().effects_applied = true;
+                ecs.item_stats.get_mut(source).unwrap().effects_applied = true;
             }
             EffectType::DamageTgt => {
 
@@ -34,7 +34,7 @@ pub fn drink_object(objects: &mut Vec<Object>, source: usize, logs: &mut LogBuff
             _ => {}
         }
     }
-    if objects[source].item_stats.as_mut().unwrap().effects_applied {
-        objects.remove(source);
+    if ecs.item_stats.get(source).unwrap().effects_applied {
+        ecs.despawn(source);
     }
-}
\ No newline at end of file
+}
@@ -2,17 +2,17 @@ use crate::prelude::*;
 use std::collections::HashSet;
 use std::ops::Neg;
 
-pub fn equip_object(objects: &mut Vec<Object>, source: usize, logs: &mut LogBuffer) {
-    let owner = objects[source].in_inventory.as_ref().unwrap().owner_id.clone();
-    let slot = objects[source].equip_slot.as_ref().unwrap().clone();
-    let name = objects[source].name.as_ref().unwrap_or(&format!("NIL")).clone();
-    let color = objects[source].render.as_ref().unwrap_or(&Render::nil_render()).clone().color.fg;
+pub fn equip_object(ecs: &mut Ecs, source: EntityId, logs: &mut LogBuffer) {
+    let owner = ecs.inventories.get(source).unwrap().owner_id;
+    let slot = *ecs.equip_slots.get(source).unwrap();
+    let name = ecs.get_name(source);
+    let color = ecs.get_fg(source);
 
     //Check all items in the owner's inventory, eliminate any slots that are used up
     let mut valid_slots: HashSet<EquipSlot> = EquipSlot::get_all_slots();
     {
-        for obj in objects.iter() {
-            if let Object { in_inventory: Some(inv), item_stats: Some(stats), equip_slot: Some(this_slot), .. } = &obj {
+        for (id, inv, stats) in ecs.inventories.join(&ecs.item_stats) {
+            if let Some(this_slot) = ecs.equip_slots.get(id) {
                 if inv.owner_id == owner {
                     if stats.equipped {
                         if this_slot == &EquipSlot::TwoHand {
@@ -40,8 +40,8 @@ pub fn equip_object(objects: &mut Vec<Object>, source: usize, logs: &mut LogBuff
         );
     }
     else {
-        objects[source].item_stats.as_mut().unwrap().equipped = true;
-        process_effect_modifiers(objects, source, false);
+        ecs.item_stats.get_mut(source).unwrap().equipped = true;
+        process_effect_modifiers(ecs, source, false);
         logs.update_logs(LogMessage::new()
             .add_part("You equip the", ColorPair::new(WHITE,GREY10))
             .add_part(format!("{}.",name), ColorPair::new(color, GREY10))
@@ -49,26 +49,26 @@ pub fn equip_object(objects: &mut Vec<Object>, source: usize, logs: &mut LogBuff
     }
 
 }
-pub fn unequip_object(objects: &mut Vec<Object>, source: usize, logs: &mut LogBuffer) {
-    let name = objects[source].name.as_ref().unwrap_or(&format!("NIL")).clone();
-    let color = objects[source].render.as_ref().unwrap_or(&Render::nil_render()).clone().color.fg;
+pub fn unequip_object(ecs: &mut Ecs, source: EntityId, logs: &mut LogBuffer) {
+    let name = ecs.get_name(source);
+    let color = ecs.get_fg(source);
 
-    objects[source].item_stats.as_mut().unwrap().equipped = false;
-    process_effect_modifiers(objects, source, true);
+    ecs.item_stats.get_mut(source).unwrap().equipped = false;
+    process_effect_modifiers(ecs, source, true);
     logs.update_logs(LogMessage::new()
         .add_part("You unequip the", ColorPair::new(WHITE,GREY10))
         .add_part(format!("{}.",name), ColorPair::new(color, GREY10))
     );
 }
 
-pub fn process_effect_modifiers(objects: &mut Vec<Object>, item_id: usize, clean: bool) {
-    let owner = objects[item_id].in_inventory.as_ref().unwrap().owner_id.clone();
+pub fn process_effect_modifiers(ecs: &mut Ecs, item_id: EntityId, clean: bool) {
+    let owner = ecs.inventories.get(item_id).unwrap().owner_id;
 
     //Collect clones of each of the items effects
     let mut effects: Vec<ItemEffect> = Vec::new();
     {
-        objects[item_id].item_stats.as_mut().unwrap().effects_applied = true;
-        for effect in &objects[item_id].item_stats.as_ref().unwrap().effects {
+        ecs.item_stats.get_mut(item_id).unwrap().effects_applied = true;
+        for effect in &ecs.item_stats.get(item_id).unwrap().effects {
             effects.push((effect.clone()));
         }
     }
@@ -84,19 +84,19 @@ pub fn process_effect_modifiers(objects: &mut Vec<Object>, item_id: usize, clean
             }
         }
     }
-    //Apply all effects to the owner
+    //Apply all effects to the owner, if it's still around
     {
-        let actor_obj = &mut objects[owner];
+        if !ecs.is_alive(owner) { return }
         for effect in effects.iter() {
             match effect.etype {
                 EffectType::HealSelf => {
-                    if let Some(health) = &mut actor_obj.health {
+                    if let Some(health) = ecs.healths.get_mut(owner) {
                         health.current += effect.params.as_ref().unwrap()[0];
                     }
                 }
                 EffectType::DamageTgt => {}
                 EffectType::WeaponDamage => {
-                    if let Some(dmg) = &mut actor_obj.damage {
+                    if let Some(dmg) = ecs.damages.get_mut(owner) {
                         if clean {
                             let (dice,val) = Damage::get_default_damage();
                             dmg.dice = dice;
@@ -109,7 +109,7 @@ pub fn process_effect_modifiers(objects: &mut Vec<Object>, item_id: usize, clean
                     }
                 }
                 EffectType::HealthUp => {
-                    if let Some(health) = &mut actor_obj.health {
+                    if let Some(health) = ecs.healths.get_mut(owner) {
                         health.max += effect.params.as_ref().unwrap()[0];
                         health.current += effect.params.as_ref().unwrap()[0];
                     }
@@ -1,34 +1,33 @@
 use crate::prelude::*;
 
-pub fn process_fov(objects: &mut Vec<Object>, map: &mut Map) {
-    let mut fovlist: Vec<usize> = Vec::new();
-    let mut ailist: Vec<usize> = Vec::new();
-    for (i,obj) in objects.iter().enumerate() {
-        if obj.viewshed.is_some() {
-            fovlist.push(i);
+pub fn process_fov(ecs: &mut Ecs, map: &mut Map, floor: i32) {
+    //Everything that sees by the light has to take another look when it changes
+    if process_lighting(ecs, map, floor) {
+        for (_, view, f) in ecs.viewsheds.join_mut(&ecs.floors) {
+            if *f == floor { view.refresh = true }
         }
     }
-    for id in fovlist.iter() {
-        if objects[*id].ai.is_some() {
-            ailist.push(*id);
-        }
-    }
-
-    for id in fovlist.iter() {
-        let pos = objects[*id].pos.as_ref().unwrap().clone();
-        let tag = objects[*id].tag.as_ref().unwrap().clone();
-        let view = objects[*id].viewshed.as_mut().unwrap();
+    let any_lit = map.light.iter().any(|l| *l >= LIT_THRESHOLD);
 
-        if view.refresh {
+    //Viewers on other floors hold onto their refresh until their floor is the active one again
+    let floors = &ecs.floors;
+    for (id, view, pos) in ecs.viewsheds.join_mut(&ecs.positions) {
+        if view.refresh && floors.get(id) == Some(&floor) {
             view.refresh = false;
             view.visible.clear();
 
-            view.visible = field_of_view(pos, view.range, map);
+            view.visible = field_of_view(*pos, view.range, map);
+            //Past their own sight range, anything in line of sight can still be seen if it's lit up
+            if any_lit {
+                for p in field_of_view(*pos, MAX_SIGHT_RANGE, map).into_iter() {
+                    if map.is_lit(p) && !view.visible.contains(&p) { view.visible.push(p) }
+                }
+            }
             view.visible.retain(|p| {
                 p.x >= 0 && p.x <= map.width - 1 && p.y >= 0 && p.y <= map.height - 1
             });
 
-            if tag == ActorTag::Player {
+            if ecs.tags.get(id) == Some(&ActorTag::Player) {
                 for t in map.visible.iter_mut() {
                     *t = false;
                 }
@@ -41,27 +40,17 @@ pub fn process_fov(objects: &mut Vec<Object>, map: &mut Map) {
         }
     }
 
-    for id in ailist.iter() {
-        //Process whether the AI target's position is within the viewshed
-        //Using scopes here to keep the borrow checker happy and not have to write .as_ref().unwrap() for everything >-<
-        let mut tgt_pos: Option<Point> = None;
-        {
-            let ai = objects[*id].ai.as_ref().unwrap();
-            if let Some(tgt_id) = ai.target {
-                tgt_pos = Some(objects[tgt_id].pos.as_ref().unwrap().clone());
-            }
-        }
-        {
-            let visible = objects[*id].viewshed.as_ref().unwrap().visible.to_vec();
-            let ai = objects[*id].ai.as_mut().unwrap();
-
-            if let Some(pos) = tgt_pos {
-                if visible.contains(&pos) {
-                    ai.tgt_heatmap.reset_to_single_node(&pos, 5);
-                } else {
-                    ai.tgt_heatmap.clear_heat_area(&visible);
-                }
+    let positions = &ecs.positions;
+    for (id, ai, view) in ecs.ais.join_mut(&ecs.viewsheds) {
+        if floors.get(id) != Some(&floor) { continue }
+        //Process whether the AI target's position is within the viewshed.
+        //A target that has since been removed from the world is simply lost track of.
+        if let (Some(pos), Some(own_pos)) = (ai.target.and_then(|tgt_id| positions.get(tgt_id)), positions.get(id)) {
+            if can_spot(map, *own_pos, view, *pos) {
+                ai.tgt_heatmap.reset_to_single_node(pos, 5);
+            } else {
+                ai.tgt_heatmap.clear_heat_area(&view.visible);
             }
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,57 @@
+use crate::prelude::*;
+
+//Lava burns for this much every turn something stands in it
+const LAVA_DAMAGE: i32 = 4;
+//Chance each turn in deep water that something carried gets lost in it
+const DEEP_WATER_DROP_CHANCE: i32 = 40;
+
+//Applies whatever the tiles under everything on this floor do to them.
+//Returns true if the player has stepped into a chasm and needs to fall to the next floor.
+pub fn proc_hazards(ecs: &mut Ecs, map: &Map, floor: i32, logs: &mut LogBuffer, rng: &mut RandomNumberGenerator) -> bool {
+    let mut standing: Vec<(EntityId, TileClass)> = Vec::new();
+    for (id, pos, _) in ecs.positions.join(&ecs.healths) {
+        if ecs.get_floor(id) == floor {
+            standing.push((id, map.tiles[map.index(pos.x, pos.y)]));
+        }
+    }
+
+    let mut player_fell = false;
+    for (id, tile) in standing.into_iter() {
+        match tile {
+            TileClass::Lava => {
+                ecs.healths.get_mut(id).unwrap().wounds.push(LAVA_DAMAGE);
+                if id == PLAYER {
+                    logs.update_logs(LogMessage::new()
+                        .add_part("The lava sears your flesh!", ColorPair::new(ORANGE, GREY10))
+                    );
+                }
+            },
+            TileClass::DeepWater => {
+                if rng.range(0, 100) < DEEP_WATER_DROP_CHANCE { lose_item_in_water(ecs, id, logs, rng) }
+            },
+            TileClass::Chasm => {
+                if id == PLAYER { player_fell = true }
+            },
+            _ => {}
+        }
+    }
+    return player_fell
+}
+
+//Something carried slips away into the water, landing on the tile underneath
+fn lose_item_in_water(ecs: &mut Ecs, owner: EntityId, logs: &mut LogBuffer, rng: &mut RandomNumberGenerator) {
+    let carried: Vec<EntityId> = ecs.inventories.iter()
+        .filter(|(_, inv)| inv.owner_id == owner)
+        .map(|(id, _)| id)
+        .collect();
+    if carried.is_empty() { return }
+
+    let item = carried[rng.range(0, carried.len())];
+    if !drop_item(ecs, item, logs) { return }
+    if owner == PLAYER {
+        logs.update_logs(LogMessage::new()
+            .add_part(format!("{}", ecs.get_name(item)), ColorPair::new(ecs.get_fg(item), GREY10))
+            .add_part("slips from your grasp into the water!", ColorPair::new(LIGHTBLUE, GREY10))
+        );
+    }
+}
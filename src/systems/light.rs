@@ -0,0 +1,109 @@
+use crate::prelude::*;
+
+//How bright a tile has to be before it can be seen from beyond a viewer's own sight range
+pub const LIT_THRESHOLD: f32 = 0.25;
+//Nothing can be made out further away than this, however brightly it's lit
+pub const MAX_SIGHT_RANGE: i32 = 16;
+//How much of its sight range a viewer has to close in by before it notices something standing in pitch darkness
+const DARK_SIGHT_SHARE: f32 = 0.5;
+//Lava glows a little onto everything around it
+const LAVA_GLOW: LightSource = LightSource { radius: 2, color: RGBA { r: 1.0, g: 0.4, b: 0.1, a: 1.0 } };
+
+//How strongly light reaches each tile, followed by the red, green and blue it brings, before the colours are mixed
+type LightMix = Vec<(f32, f32, f32, f32)>;
+
+//What a floor's light was last worked out from, so it only has to be worked out again when that changes.
+//The lava's glow is kept apart, since it only shifts when a door opens or closes.
+#[derive(Clone, Default)]
+pub struct LightCache {
+    glow: Option<LightMix>,
+    sources: Vec<(Point, LightSource)>
+}
+impl LightCache {
+    //Has the light worked out again from scratch next time round
+    pub fn invalidate(&mut self) {
+        self.glow = None;
+    }
+}
+
+//Works out how brightly lit every tile of the active floor is, from the light sources on it and any lava.
+//Overlapping lights add up, and their colours mix by how strongly each one reaches a tile.
+//Returns true if the light changed, so anything that sees by it knows to take another look.
+pub fn process_lighting(ecs: &Ecs, map: &mut Map, floor: i32) -> bool {
+    let mut sources: Vec<(Point, LightSource)> = Vec::new();
+    for (id, source) in ecs.lights.iter() {
+        //Carried lights shine from whoever is carrying them
+        let holder = ecs.inventories.get(id).map_or(id, |inv| inv.owner_id);
+        if ecs.get_floor(holder) != floor { continue }
+        if let Some(pos) = ecs.positions.get(holder) { sources.push((*pos, *source)) }
+    }
+    //Nothing has moved and no door has changed, so the light is just as it was
+    if map.light_cache.glow.is_some() && sources == map.light_cache.sources { return false }
+
+    let glow = match map.light_cache.glow.take() {
+        Some(glow) => glow,
+        None => {
+            let mut glow = vec![(0.0, 0.0, 0.0, 0.0); map.tiles.len()];
+            for (idx, tile) in map.tiles.iter().enumerate() {
+                if *tile == TileClass::Lava { shine(map, map.point_from_idx(idx), &LAVA_GLOW, &mut glow) }
+            }
+            glow
+        }
+    };
+    let mut mix = glow.clone();
+    for (pos, source) in sources.iter() {
+        shine(map, *pos, source, &mut mix);
+    }
+
+    let colors: Vec<RGBA> = mix.iter().map(|(l, r, g, b)| {
+        if *l > 0.0 { RGBA::from_f32(r / l, g / l, b / l, 1.0) } else { RGBA::from_f32(0.0, 0.0, 0.0, 1.0) }
+    }).collect();
+    let light: Vec<f32> = mix.iter().map(|(l, _, _, _)| f32::min(*l, 1.0)).collect();
+
+    let changed = light != map.light;
+    map.light = light;
+    map.light_color = colors;
+    map.light_cache = LightCache { glow: Some(glow), sources };
+    return changed
+}
+
+//Adds the light from one source onto every tile it reaches
+fn shine(map: &Map, pos: Point, source: &LightSource, mix: &mut LightMix) {
+    for p in field_of_view(pos, source.radius, map).iter() {
+        let idx = match map.try_index(p.x, p.y) {
+            Some(idx) => idx,
+            None => continue
+        };
+        let strength = 1.0 - DistanceAlg::Pythagoras.distance2d(pos, *p) / (source.radius + 1) as f32;
+        if strength <= 0.0 { continue }
+
+        mix[idx].0 += strength;
+        mix[idx].1 += source.color.r * strength;
+        mix[idx].2 += source.color.g * strength;
+        mix[idx].3 += source.color.b * strength;
+    }
+}
+
+//Whether a viewer can pick out something standing on a tile it has in view. Anything on a lit tile stands out as far
+//as the viewer can see, but the darker the tile, the closer the viewer has to be to notice what's there.
+pub fn can_spot(map: &Map, viewer: Point, view: &Viewshed, target: Point) -> bool {
+    if !view.visible.contains(&target) { return false }
+
+    let light = map.try_index(target.x, target.y).map_or(0.0, |idx| map.light.get(idx).cloned().unwrap_or(0.0));
+    if light >= LIT_THRESHOLD { return true }
+
+    let share = DARK_SIGHT_SHARE + (1.0 - DARK_SIGHT_SHARE) * light / LIT_THRESHOLD;
+    //Nothing gets past a viewer right next to it, however dark it is
+    let range = f32::max(view.range as f32 * share, 1.5);
+    return DistanceAlg::Pythagoras.distance2d(viewer, target) <= range
+}
+
+//True for an enemy the player can't pick out of the dark, which keeps it off the screen and out of the player's memory
+pub fn hidden_in_dark(ecs: &Ecs, map: &Map, id: EntityId) -> bool {
+    if ecs.tags.get(id) != Some(&ActorTag::Enemy) { return false }
+
+    return match (ecs.positions.get(PLAYER), ecs.viewsheds.get(PLAYER), ecs.positions.get(id)) {
+        (Some(viewer), Some(view), Some(pos)) => !can_spot(map, *viewer, view, *pos),
+        _ => false
+    }
+}
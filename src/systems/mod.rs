@@ -5,6 +5,10 @@ mod combat;
 mod playermem;
 mod equip;
 mod drink;
+mod hazard;
+mod trap;
+mod secret;
+mod light;
 
 pub use fov::*;
 pub use blockmove::*;
@@ -12,4 +16,8 @@ pub use ai::*;
 pub use combat::*;
 pub use playermem::*;
 pub use equip::*;
-pub use drink::*;
\ No newline at end of file
+pub use drink::*;
+pub use hazard::*;
+pub use trap::*;
+pub use secret::*;
+pub use light::*;
\ No newline at end of file
@@ -1,30 +1,40 @@
 use crate::prelude::*;
 
-pub fn update_player_memory(objects: &mut Vec<Object>) {
+pub fn update_player_memory(ecs: &mut Ecs, map: &Map) {
     let visible =
-        if let Some(view) = &objects[0].viewshed {
+        if let Some(view) = ecs.viewsheds.get(PLAYER) {
             view.visible.to_vec()
         }
         else {
             Vec::new()
         };
+    //Enemies lurking in the dark go unnoticed, so there's nothing to remember them by
+    let lurking: Vec<EntityId> = ecs.tags.iter()
+        .map(|(id, _)| id)
+        .filter(|id| hidden_in_dark(ecs, map, *id))
+        .collect();
 
-    for obj in objects.iter_mut() {
-        if let Object { pos: Some(pos), player_mem: mem, .. } = obj {
-            //Clear out the player memory if that spot has been seen again
-            if mem.last_pos.is_some() {
-                if visible.contains(mem.last_pos.as_ref().unwrap()) {
-                    mem.seen = false;
-                    mem.last_pos = None;
-                }
+    let floor = ecs.get_floor(PLAYER);
+
+    let traps = &ecs.traps;
+    let floors = &ecs.floors;
+    for (id, mem, pos) in ecs.memories.join_mut(&ecs.positions) {
+        //Traps nobody has found yet aren't remembered, and other floors are left as they were
+        if traps.get(id).map_or(false, |trap| !trap.revealed) || floors.get(id) != Some(&floor) { continue }
+
+        //Clear out the player memory if that spot has been seen again
+        if mem.last_pos.is_some() {
+            if visible.contains(mem.last_pos.as_ref().unwrap()) {
+                mem.seen = false;
+                mem.last_pos = None;
             }
+        }
 
-            'inner: for p in visible.iter() {
-                if p == pos {
-                    mem.seen = true;
-                    mem.last_pos = Some(*p);
-                    break 'inner;
-                }
+        'inner: for p in visible.iter() {
+            if p == pos && !lurking.contains(&id) {
+                mem.seen = true;
+                mem.last_pos = Some(*p);
+                break 'inner;
             }
         }
     }
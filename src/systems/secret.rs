@@ -0,0 +1,29 @@
+use crate::prelude::*;
+
+//Percent chance of finding a secret door right next to the searcher; it halves at two tiles away,
+//drops to a third at three, and so on out to the edge of what they can see
+const SECRET_SEARCH_CHANCE: i32 = 60;
+
+//Looks over everything an entity can see for secret doors, turning any found into plain doors.
+//Returns how many were found.
+pub fn search_for_secrets(ecs: &mut Ecs, searcher: EntityId, map: &mut Map, logs: &mut LogBuffer, rng: &mut RandomNumberGenerator) -> usize {
+    let (origin, visible) = match (ecs.positions.get(searcher), ecs.viewsheds.get(searcher)) {
+        (Some(pos), Some(view)) => (*pos, view.visible.to_vec()),
+        _ => return 0
+    };
+
+    let mut found = 0;
+    for pos in visible.into_iter() {
+        if map.tiles[map.index(pos.x, pos.y)] != TileClass::SecretDoor { continue }
+
+        let distance = DistanceAlg::Pythagoras.distance2d(origin, pos).round().max(1.0) as i32;
+        if rng.range(0, 100) < SECRET_SEARCH_CHANCE / distance && map.reveal_secret(pos) {
+            found += 1;
+            logs.update_logs(LogMessage::new()
+                .add_part("You find a", ColorPair::new(WHITE, GREY10))
+                .add_part("secret door!", ColorPair::new(CHOCOLATE, GREY10))
+            );
+        }
+    }
+    return found
+}
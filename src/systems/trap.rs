@@ -0,0 +1,174 @@
+use crate::prelude::*;
+
+//Darts hit for 1d this much
+const DART_DAMAGE: i32 = 6;
+//How far an alarm carries, and how long whatever hears it goes looking
+const ALARM_RADIUS: f32 = 16.0;
+const ALARM_MEMORY: u16 = 24;
+//Percent chances of noticing a hidden trap in view each turn, of finding one while searching,
+//and of disarming one that's been found
+const TRAP_SPOT_CHANCE: i32 = 10;
+const TRAP_SEARCH_CHANCE: i32 = 75;
+const TRAP_DISARM_CHANCE: i32 = 60;
+const SEARCH_RADIUS: f32 = 2.0;
+
+//Works out what every trap sprung this turn does to whatever sprung it.
+//Returns true if the player went through a trapdoor and needs to fall to the next floor.
+pub fn proc_traps(ecs: &mut Ecs, map: &Map, floor: i32, logs: &mut LogBuffer, rng: &mut RandomNumberGenerator) -> bool {
+    let mut sprung: Vec<(EntityId, TrapKind, EntityId)> = Vec::new();
+    for (id, trap) in ecs.traps.iter_mut() {
+        if let Some(victim) = trap.triggered_by.take() { sprung.push((id, trap.kind, victim)) }
+    }
+
+    let mut player_fell = false;
+    for (trap_id, kind, victim) in sprung.into_iter() {
+        if !ecs.is_alive(victim) { continue }
+        let trap_pos = *ecs.positions.get(trap_id).unwrap();
+        let seen = victim == PLAYER || map.visible[map.index(trap_pos.x, trap_pos.y)];
+        if seen {
+            reveal_trap(ecs, trap_id);
+            let (name, verb) = {
+                if victim == PLAYER {
+                    (String::from("You"), String::from("set"))
+                }
+                else {
+                    (ecs.get_name(victim), String::from("sets"))
+                }
+            };
+            logs.update_logs(LogMessage::new()
+                .add_part(name, ColorPair::new(ecs.get_fg(victim), GREY10))
+                .add_part(format!("{} off", verb), ColorPair::new(WHITE, GREY10))
+                .add_part(format!("{}!", ecs.get_name(trap_id)), ColorPair::new(ecs.get_fg(trap_id), GREY10))
+            );
+        }
+
+        match kind {
+            TrapKind::Dart => {
+                if let Some(health) = ecs.healths.get_mut(victim) { health.wounds.push(rng.roll_dice(1, DART_DAMAGE)) }
+            },
+            TrapKind::Teleport => teleport_randomly(ecs, victim, map, rng),
+            TrapKind::Alarm => sound_alarm(ecs, trap_pos, floor),
+            TrapKind::Trapdoor => {
+                if victim == PLAYER { player_fell = true }
+                //Anything else that drops through is gone for good
+                else { ecs.despawn(victim) }
+            }
+        }
+    }
+    return player_fell
+}
+
+//Gives the player a chance to notice each hidden trap they can see
+pub fn spot_traps(ecs: &mut Ecs, logs: &mut LogBuffer, rng: &mut RandomNumberGenerator) {
+    let visible = match ecs.viewsheds.get(PLAYER) {
+        Some(view) => view.visible.to_vec(),
+        None => return
+    };
+    let floor = ecs.get_floor(PLAYER);
+
+    let mut spotted: Vec<EntityId> = Vec::new();
+    for (id, pos, trap) in ecs.positions.join(&ecs.traps) {
+        if !trap.revealed && ecs.get_floor(id) == floor && visible.contains(pos) && rng.range(0, 100) < TRAP_SPOT_CHANCE {
+            spotted.push(id);
+        }
+    }
+    for id in spotted.into_iter() { found_trap(ecs, id, logs, "You spot") }
+}
+
+//Looks over the tiles around an entity for hidden traps. Returns how many were found.
+pub fn search_for_traps(ecs: &mut Ecs, searcher: EntityId, logs: &mut LogBuffer, rng: &mut RandomNumberGenerator) -> usize {
+    let origin = match ecs.positions.get(searcher) {
+        Some(pos) => *pos,
+        None => return 0
+    };
+    let floor = ecs.get_floor(searcher);
+
+    let mut found: Vec<EntityId> = Vec::new();
+    for (id, pos, trap) in ecs.positions.join(&ecs.traps) {
+        let nearby = DistanceAlg::Pythagoras.distance2d(origin, *pos) <= SEARCH_RADIUS;
+        if !trap.revealed && ecs.get_floor(id) == floor && nearby && rng.range(0, 100) < TRAP_SEARCH_CHANCE {
+            found.push(id);
+        }
+    }
+    let count = found.len();
+    for id in found.into_iter() { found_trap(ecs, id, logs, "You find") }
+    return count
+}
+
+//Tries to take apart a found trap on or next to an entity. Returns false if there was nothing to disarm.
+pub fn try_disarm_trap(ecs: &mut Ecs, id: EntityId, logs: &mut LogBuffer, rng: &mut RandomNumberGenerator) -> bool {
+    let origin = match ecs.positions.get(id) {
+        Some(pos) => *pos,
+        None => return false
+    };
+    let floor = ecs.get_floor(id);
+
+    let target = ecs.positions.join(&ecs.traps)
+        .find(|(trap_id, pos, trap)| {
+            trap.revealed && ecs.get_floor(*trap_id) == floor && (**pos - origin).x.abs() <= 1 && (**pos - origin).y.abs() <= 1
+        })
+        .map(|(trap_id, _, _)| trap_id);
+    let trap_id = match target {
+        Some(trap_id) => trap_id,
+        None => {
+            logs.update_logs(LogMessage::new()
+                .add_part("There is no trap here to disarm!", ColorPair::new(GREY65, GREY10))
+            );
+            return false
+        }
+    };
+
+    let name = ecs.get_name(trap_id);
+    let color = ColorPair::new(ecs.get_fg(trap_id), GREY10);
+    if rng.range(0, 100) < TRAP_DISARM_CHANCE {
+        logs.update_logs(LogMessage::new()
+            .add_part("You disarm the", ColorPair::new(WHITE, GREY10))
+            .add_part(format!("{}.", name), color)
+        );
+        ecs.despawn(trap_id);
+    } else {
+        logs.update_logs(LogMessage::new()
+            .add_part("You fail to disarm the", ColorPair::new(WHITE, GREY10))
+            .add_part(format!("{}.", name), color)
+        );
+    }
+    return true
+}
+
+fn found_trap(ecs: &mut Ecs, id: EntityId, logs: &mut LogBuffer, verb: &str) {
+    reveal_trap(ecs, id);
+    logs.update_logs(LogMessage::new()
+        .add_part(verb, ColorPair::new(WHITE, GREY10))
+        .add_part(format!("a {}!", ecs.get_name(id)), ColorPair::new(ecs.get_fg(id), GREY10))
+    );
+}
+fn reveal_trap(ecs: &mut Ecs, id: EntityId) {
+    if let Some(trap) = ecs.traps.get_mut(id) { trap.revealed = true }
+}
+
+//Moves something to a random open floor tile somewhere else on the map
+fn teleport_randomly(ecs: &mut Ecs, id: EntityId, map: &Map, rng: &mut RandomNumberGenerator) {
+    for _ in 0..1000 {
+        let dest = Point::new(rng.range(0, map.width), rng.range(0, map.height));
+        if map.walkable(dest.x, dest.y) && map.tiles[map.index(dest.x, dest.y)] == TileClass::Floor {
+            ecs.positions.insert(id, dest);
+            if let Some(view) = ecs.viewsheds.get_mut(id) { view.refresh = true }
+            return
+        }
+    }
+}
+
+//Everything with an AI close enough to hear the alarm comes to see what set it off
+fn sound_alarm(ecs: &mut Ecs, origin: Point, floor: i32) {
+    let positions = &ecs.positions;
+    let floors = &ecs.floors;
+    for (id, ai) in ecs.ais.iter_mut() {
+        let in_earshot = positions.get(id).map_or(false, |pos| DistanceAlg::Pythagoras.distance2d(*pos, origin) <= ALARM_RADIUS);
+        if in_earshot && floors.get(id) == Some(&floor) {
+            ai.target = Some(PLAYER);
+            ai.state = AIState::Hunting;
+            ai.tgt_memory = ALARM_MEMORY;
+            ai.tgt_heatmap.reset_to_single_node(&origin, 5);
+        }
+    }
+}
@@ -0,0 +1,250 @@
+use crate::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+
+//Branch entrances are picked with a generator of their own, keyed by a floor that never exists
+const BRANCH_PLACEMENT_KEY: i32 = -1;
+
+#[derive(Serialize,Deserialize)]
+pub struct World {
+    pub seed: u64,
+    pub rng: RandomNumberGenerator,
+    pub ecs: Ecs,
+    pub active_map: Map,
+    //Every floor that has been left behind, by floor key
+    pub stored_maps: BTreeMap<i32, Map>,
+    //The side branches in this run, and which one the player is in. Depth counts from the top of the branch.
+    pub branches: Vec<Branch>,
+    pub branch: u32,
+    pub depth: i32,
+    //The bottom of the main dungeon, and the boss and goal item waiting there
+    pub final_depth: i32,
+    pub boss_id: u32,
+    pub goal_item_id: u32,
+    pub turns: u32,
+    pub permadeath: bool,
+    //Every action the player has taken this run, so its replay can be picked back up after a reload.
+    //Runs saved before this was kept have none, and can't be replayed.
+    pub action_log: Option<Vec<Actions>>,
+    #[serde(skip)]
+    pub save_slot: Option<usize>,
+    pub camera: Camera,
+}
+impl World {
+    pub fn empty() -> World {
+        World {
+            seed: 0,
+            rng: RandomNumberGenerator::new(),
+            ecs: Ecs::new(),
+            active_map: Map::new(0,0),
+            stored_maps: BTreeMap::new(),
+            branches: Vec::new(),
+            branch: MAIN_DUNGEON,
+            depth: 0,
+            final_depth: DEFAULT_FINAL_DEPTH,
+            boss_id: 0,
+            goal_item_id: 0,
+            turns: 0,
+            permadeath: false,
+            action_log: None,
+            save_slot: None,
+            camera: Camera::new(Point::zero()),
+        }
+    }
+    pub fn new_game(seed: u64) -> World {
+        let conn = open_connection();
+        let mut branches = import_branches(&conn).unwrap_or(vec![]);
+        conn.close().expect("Connection to SQLite DB failed to close.");
+        let endgame = Endgame::load();
+
+        //Branches have to start somewhere above the final floor
+        for branch in branches.iter_mut() {
+            branch.entry_max_depth = branch.entry_max_depth.min(endgame.final_depth - 1);
+        }
+
+        let mut world = World {
+            seed,
+            rng: RandomNumberGenerator::seeded(seed),
+            ecs: Ecs::new(),
+            active_map: Map::new(0, 0),
+            stored_maps: BTreeMap::new(),
+            branches: Branch::place_entrances(branches, &mut World::floor_rng(seed, BRANCH_PLACEMENT_KEY)),
+            branch: MAIN_DUNGEON,
+            depth: 1,
+            final_depth: endgame.final_depth,
+            boss_id: endgame.boss_id,
+            goal_item_id: endgame.goal_item_id,
+            turns: 0,
+            permadeath: false,
+            action_log: Some(Vec::new()),
+            save_slot: None,
+            camera: Camera::new(Point::zero()),
+        };
+        let mapgen = world.generate_floor(MAIN_DUNGEON, 1);
+        let startpos = mapgen.start_pos;
+        world.active_map = mapgen.map;
+        world.camera = Camera::new(startpos);
+
+        //Spawn the player; being the first entity gives it the PLAYER handle
+        let player = spawn_player(startpos);
+        world.ecs.spawn(player);
+
+        //Spawn starting equipment in the player's inventory
+        let start_equip: Vec<Object> = get_starting_equip();
+        for item in start_equip.into_iter() {
+            world.ecs.spawn(item);
+        }
+
+        //Place all objects generated alongside the map into the game world
+        for obj in mapgen.objects.into_iter() {
+            world.ecs.spawn(obj);
+        }
+
+        return world;
+    }
+    pub fn descend_to_next(&mut self) {
+        self.change_floor(self.branch, self.depth + 1, TileClass::UpStair);
+    }
+    pub fn ascend_to_previous(&mut self) {
+        //The top of a branch leads back out to wherever it was entered from
+        match self.current_branch() {
+            Some(branch) if self.depth == 1 => {
+                let entry = branch.entry_depth;
+                self.change_floor(MAIN_DUNGEON, entry, TileClass::BranchStair)
+            },
+            _ => self.change_floor(self.branch, self.depth - 1, TileClass::DownStair)
+        }
+    }
+    //Heads down into whichever branch starts on the current floor
+    pub fn enter_branch(&mut self) {
+        if self.branch != MAIN_DUNGEON { return }
+        if let Some(branch) = self.branches.iter().find(|b| b.entry_depth == self.depth) {
+            let id = branch.id;
+            self.change_floor(id, 1, TileClass::UpStair)
+        }
+    }
+
+    //The floor key of wherever the player is now
+    pub fn floor(&self) -> i32 {
+        return floor_key(self.branch, self.depth)
+    }
+    //How this run is won, as it was set when the run began
+    pub fn endgame(&self) -> Endgame {
+        return Endgame { final_depth: self.final_depth, boss_id: self.boss_id, goal_item_id: self.goal_item_id }
+    }
+    pub fn current_branch(&self) -> Option<&Branch> {
+        return self.branches.iter().find(|b| b.id == self.branch)
+    }
+    //Describes a floor for the logs, e.g. "level 3" or "the Fungal Cave, level 2"
+    pub fn level_name(&self, branch: u32, depth: i32) -> String {
+        return match self.branches.iter().find(|b| b.id == branch) {
+            Some(b) => format!("the {}, level {}", b.name, depth),
+            None => format!("level {}", depth)
+        }
+    }
+
+    //Builds a floor that hasn't been visited yet, using its branch's profile. Main dungeon floors get the
+    //stairs into any branch that starts there, the bottom floor of a branch has no way further down,
+    //and the final floor of the main dungeon holds the boss and the goal item.
+    fn generate_floor(&mut self, branch: u32, depth: i32) -> MapGenerator {
+        let key = floor_key(branch, depth);
+        let mut rng = World::floor_rng(self.seed, key);
+
+        let mut mapgen = match self.branches.iter().find(|b| b.id == branch) {
+            Some(b) => {
+                let mut mapgen = MapGenerator::from_profile(&LevelProfile::for_branch(b.id, depth), b.difficulty(depth), &mut rng);
+                if depth >= b.floors { mapgen.seal_bottom() }
+                mapgen
+            },
+            None => {
+                let mut mapgen = MapGenerator::for_depth(depth, &mut rng);
+                if depth >= self.final_depth {
+                    let (boss, goal) = get_final_encounter(&self.endgame());
+                    mapgen.place_final_encounter(boss, goal);
+                }
+                //A branch whose stairs can't go anywhere is left out of the run, rather than listed but unreachable
+                else if self.branches.iter().any(|b| b.entry_depth == depth) && !mapgen.place_branch_stairs(&mut rng) {
+                    self.branches.retain(|b| b.entry_depth != depth);
+                }
+                mapgen
+            }
+        };
+        for obj in mapgen.objects.iter_mut() {
+            obj.floor = key;
+        }
+        return mapgen
+    }
+
+    //Swaps the active map out for another floor, restoring it as it was left if it has been visited before.
+    //The player arrives on the given kind of stairs, or as close to them as they can get.
+    fn change_floor(&mut self, branch: u32, depth: i32, arrive_on: TileClass) {
+        let key = floor_key(branch, depth);
+        let next_map = match self.stored_maps.remove(&key) {
+            Some(map) => map,
+            None => {
+                let mapgen = self.generate_floor(branch, depth);
+                for obj in mapgen.objects.into_iter() {
+                    self.ecs.spawn(obj);
+                }
+                mapgen.map
+            }
+        };
+        let last_map = std::mem::replace(&mut self.active_map, next_map);
+        self.stored_maps.insert(self.floor(), last_map);
+        self.branch = branch;
+        self.depth = depth;
+
+        //Blocked tiles are worked out before the player shows up, so they don't get in their own way
+        update_blocked_tiles(&self.ecs, &mut self.active_map, key);
+        let stairs = self.active_map.tiles.iter().position(|t| *t == arrive_on)
+            .map_or(Point::zero(), |idx| self.active_map.point_from_idx(idx));
+        let arrival = self.free_spot_near(stairs);
+
+        self.ecs.floors.insert(PLAYER, key);
+        self.ecs.positions.insert(PLAYER, arrival);
+        self.camera = Camera::new(arrival);
+
+        //Everything on the floor takes a fresh look around, since the map may have changed under them
+        for (_, view, floor) in self.ecs.viewsheds.join_mut(&self.ecs.floors) {
+            if *floor == key { view.refresh = true }
+        }
+        for t in self.active_map.visible.iter_mut() {
+            *t = false;
+        }
+
+        self.clean_garbage_objects();
+    }
+
+    //Finds the closest tile to a point that can be stood on safely
+    fn free_spot_near(&self, target: Point) -> Point {
+        let map = &self.active_map;
+        let mut best: Option<(f32, Point)> = None;
+        for (idx, tile) in map.tiles.iter().enumerate() {
+            let pos = map.point_from_idx(idx);
+            if tile.is_hazard() || !map.walkable(pos.x, pos.y) { continue }
+
+            let distance = DistanceAlg::Pythagoras.distance2d(target, pos);
+            if best.map_or(true, |(d, _)| distance < d) { best = Some((distance, pos)) }
+        }
+        return best.map_or(target, |(_, pos)| pos)
+    }
+
+    //Each floor gets its own generator derived from the run's seed and its floor key, so floors don't depend on what
+    //happened above them
+    pub fn floor_rng(seed: u64, key: i32) -> RandomNumberGenerator {
+        RandomNumberGenerator::seeded(seed ^ (key as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+    }
+
+    fn clean_garbage_objects(&mut self) {
+        //Items are only kept around while whatever was carrying them still exists
+        let mut removelist: Vec<EntityId> = Vec::new();
+        for (i, inv) in self.ecs.inventories.iter() {
+            if !self.ecs.is_alive(inv.owner_id) {
+                removelist.push(i);
+            }
+        }
+        for id in removelist.iter() {
+            self.ecs.despawn(*id);
+        }
+    }
+}
@@ -0,0 +1,126 @@
+mod common;
+use common::*;
+use darkdelver::prelude::*;
+
+//Walks down the main dungeon until the floor a branch starts on
+fn game_at_branch_entrance(seed: u64) -> (Game, Branch) {
+    let mut game = game_from_seed(seed);
+    let branch = game.world.branches[0].clone();
+    while game.world.depth < branch.entry_depth {
+        take_stairs(&mut game, TileClass::DownStair, Actions::TryGoDown);
+    }
+    return (game, branch)
+}
+
+#[test]
+fn branches_load_from_the_database() {
+    let conn = open_connection();
+    let branches = import_branches(&conn).expect("No branches found in the database.");
+    assert!(!branches.is_empty());
+
+    for branch in branches.iter() {
+        assert!(branch.entry_min_depth >= 1 && branch.entry_min_depth <= branch.entry_max_depth, "{} has a bad depth range", branch.name);
+        assert!(branch.floors > 0);
+        for depth in 1..=branch.floors {
+            let profile = import_level_profile(&conn, branch.id, depth).expect("Branch floor has no level profile");
+            assert!(get_spawn_table_info(&conn, profile.spawn_table).map_or(false, |t| !t.is_empty()), "{} has no spawn table", branch.name);
+        }
+    }
+}
+
+#[test]
+fn each_run_places_branch_entrances_on_separate_floors() {
+    for seed in 0..10 {
+        let game = game_from_seed(seed);
+        let branches = &game.world.branches;
+        assert!(!branches.is_empty());
+
+        for (i, branch) in branches.iter().enumerate() {
+            assert!(branch.entry_depth >= branch.entry_min_depth && branch.entry_depth <= branch.entry_max_depth);
+            assert!(branches.iter().skip(i + 1).all(|b| b.entry_depth != branch.entry_depth));
+        }
+    }
+    let a = game_from_seed(42);
+    let b = game_from_seed(42);
+    assert!(a.world.branches.iter().zip(b.world.branches.iter()).all(|(a, b)| a.entry_depth == b.entry_depth));
+}
+
+#[test]
+fn branch_stairs_lead_into_the_branch_and_back() {
+    let (mut game, branch) = game_at_branch_entrance(3);
+    let map = &game.world.active_map;
+    assert_eq!(map.tiles.iter().filter(|t| **t == TileClass::BranchStair).count(), 1);
+    let entrance = map.point_from_idx(map.tiles.iter().position(|t| *t == TileClass::BranchStair).unwrap());
+    assert!(reachable_from(map, player_pos(&game))[map.index(entrance.x, entrance.y)]);
+
+    take_stairs(&mut game, TileClass::BranchStair, Actions::TryGoDown);
+    assert_eq!(game.world.branch, branch.id);
+    assert_eq!(game.world.depth, 1);
+    assert!(game.world.current_branch().is_some());
+    assert!(logged(&game, &format!("Descending to the {}, level 1...", branch.name)));
+    assert_eq!(game.world.ecs.get_floor(PLAYER), floor_key(branch.id, 1));
+
+    take_stairs(&mut game, TileClass::UpStair, Actions::TryGoUp);
+    assert_eq!(game.world.branch, MAIN_DUNGEON);
+    assert_eq!(game.world.depth, branch.entry_depth);
+    assert_eq!(player_pos(&game), entrance);
+    assert!(logged(&game, &format!("Ascending to level {}...", branch.entry_depth)));
+}
+
+#[test]
+fn branch_floors_are_built_from_the_branch_tables() {
+    let (mut game, branch) = game_at_branch_entrance(8);
+    take_stairs(&mut game, TileClass::BranchStair, Actions::TryGoDown);
+
+    let profile = LevelProfile::for_branch(branch.id, 1);
+    assert_eq!((game.world.active_map.width, game.world.active_map.height), (profile.width, profile.height));
+
+    //Everything spawned down here belongs to the branch floor, and its enemies come from the branch's table
+    let conn = open_connection();
+    let allowed: Vec<String> = get_spawn_table_info(&conn, profile.spawn_table).unwrap().iter()
+        .flat_map(|e| import_enemies_to_objects(&conn, String::from("V_EnemiesFull"), Some(format!("id = {}", e.enemy_id))).unwrap())
+        .map(|o| o.name.unwrap())
+        .collect();
+
+    let key = floor_key(branch.id, 1);
+    let mut enemies = 0;
+    for (id, floor) in game.world.ecs.floors.iter() {
+        if *floor != key || game.world.ecs.ais.get(id).is_none() { continue }
+        enemies += 1;
+        assert!(allowed.contains(&&game.world.ecs.get_name(id)), "{} does not belong in the {}", game.world.ecs.get_name(id), branch.name);
+    }
+    assert!(enemies > 0);
+}
+
+#[test]
+fn the_bottom_of_a_branch_goes_no_further() {
+    let (mut game, branch) = game_at_branch_entrance(5);
+    take_stairs(&mut game, TileClass::BranchStair, Actions::TryGoDown);
+    while game.world.depth < branch.floors {
+        take_stairs(&mut game, TileClass::DownStair, Actions::TryGoDown);
+    }
+
+    assert_eq!(game.world.branch, branch.id);
+    let map = &game.world.active_map;
+    assert!(!map.tiles.iter().any(|t| *t == TileClass::DownStair || *t == TileClass::Chasm));
+    let key = game.world.floor();
+    assert!(!game.world.ecs.traps.iter().any(|(id, trap)| trap.kind == TrapKind::Trapdoor && game.world.ecs.get_floor(id) == key));
+}
+
+#[test]
+fn branch_stairs_still_go_in_when_every_other_room_is_taken() {
+    let mut rng = RandomNumberGenerator::seeded(6);
+    let mut mapgen = MapGenerator::for_depth(2, &mut rng);
+    let taken: Vec<Point> = mapgen.regions.iter().skip(1).flat_map(|r| r.points.iter().cloned()).collect();
+    for pos in taken.into_iter() {
+        let mut obj = make_corpse(pos, 2);
+        obj.pos = Some(pos);
+        mapgen.objects.push(obj);
+    }
+
+    assert!(mapgen.place_branch_stairs(&mut rng));
+    let map = &mapgen.map;
+    let idx = map.tiles.iter().position(|t| *t == TileClass::BranchStair).unwrap();
+    assert!(reachable_from(map, mapgen.start_pos)[idx]);
+    assert!(map.point_from_idx(idx) != mapgen.start_pos);
+}
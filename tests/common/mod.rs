@@ -0,0 +1,161 @@
+//Shared helpers for building worlds and driving turns without a console
+#![allow(dead_code)]
+use darkdelver::prelude::*;
+
+//Builds a game on a hand-written map. Legend:
+//  '#' wall, '.' floor, '>' down stairs, '+' closed door, '\'' open door, '"' glass,
+//  '~' shallow water, '=' deep water, '%' lava, ':' chasm,
+//  '@' the player, 'e' a test dummy enemy, '!' a Potion of Mending, '/' an Iron Longsword, '*' the goal item, 't' a torch
+//Everything that isn't a wall is floor underneath.
+pub fn game_from_ascii(rows: &[&str]) -> Game {
+    let w = rows.iter().map(|r| r.chars().count()).max().unwrap_or(0) as i32;
+    let h = rows.len() as i32;
+    let mut world = World::empty();
+    world.active_map = Map::new(w, h);
+    world.depth = 1;
+    world.rng = RandomNumberGenerator::seeded(0);
+
+    let mut player_pos: Option<Point> = None;
+    let mut spawns: Vec<(char, Point)> = Vec::new();
+    for (y, row) in rows.iter().enumerate() {
+        for (x, c) in row.chars().enumerate() {
+            let pos = Point::new(x as i32, y as i32);
+            let idx = world.active_map.index(pos.x, pos.y);
+            world.active_map.tiles[idx] = match c {
+                '#' => TileClass::Wall,
+                '>' => TileClass::DownStair,
+                '<' => TileClass::UpStair,
+                '+' => TileClass::DoorClosed,
+                'S' => TileClass::SecretDoor,
+                '"' => TileClass::Glass,
+                '~' => TileClass::ShallowWater,
+                '=' => TileClass::DeepWater,
+                '%' => TileClass::Lava,
+                ':' => TileClass::Chasm,
+                '\'' => TileClass::DoorOpen,
+                _ => TileClass::Floor
+            };
+            match c {
+                '@' => player_pos = Some(pos),
+                'e' | '!' | '/' | '*' | 't' => spawns.push((c, pos)),
+                _ => {}
+            }
+        }
+    }
+
+    //The player has to be spawned first to get the PLAYER handle
+    let start = player_pos.expect("Test maps need an '@' for the player");
+    world.ecs.spawn(spawn_player(start));
+    world.camera = Camera::new(start);
+
+    for (c, pos) in spawns.into_iter() {
+        let mut obj = match c {
+            'e' => test_dummy(),
+            '!' => item_prefab(2),
+            '*' => get_final_encounter(&Endgame::load()).1,
+            't' => make_torch(),
+            _ => item_prefab(1)
+        };
+        add_positional_info(&mut obj, pos, 1);
+        world.ecs.spawn(obj);
+    }
+
+    let mut game = Game::from_world(world);
+    game.exec_all_systems();
+    return game
+}
+
+//Builds a game the same way a new run does
+pub fn game_from_seed(seed: u64) -> Game {
+    let mut game = Game::new(seed, false);
+    game.exec_all_systems();
+    return game
+}
+
+//Puts the player on the first tile of a kind on the current floor and takes an action there
+pub fn take_stairs(game: &mut Game, stairs: TileClass, action: Actions) {
+    let map = &game.world.active_map;
+    let idx = map.tiles.iter().position(|t| *t == stairs).expect("No stairs of that kind on this floor");
+    let pos = map.point_from_idx(idx);
+    game.world.ecs.positions.insert(PLAYER, pos);
+    game.advance_turn(action);
+}
+
+//A weak enemy that always hits for exactly 1, so fights play out the same every time
+pub fn test_dummy() -> Object {
+    Object {
+        name: Some("Test Dummy".to_string()),
+        tag: Some(ActorTag::Enemy),
+        render: Some(Render { glyph: 100, color: ColorPair::new(RED, BLACK), order: 10 }),
+        viewshed: Some(Viewshed { range: 5, visible: Vec::new(), refresh: true }),
+        block_tile: true,
+        initiative: Some(1),
+        health: Some(Health::new(3)),
+        damage: Some(Damage::new((1, 1))),
+        ai: Some(AIClass::new()),
+        ..Object::blank()
+    }
+}
+
+//Pulls an item template out of the content database by its ID
+pub fn item_prefab(id: i32) -> Object {
+    let conn = open_connection();
+    let items = import_items_to_objects(&conn, String::from("V_ItemsFull"), Some(format!("id = {}", id)))
+        .expect("Failed to import a test item from the database.");
+    return items[0].clone()
+}
+
+//Looks up the first living entity with the given name
+pub fn find_named(game: &Game, name: &str) -> Option<EntityId> {
+    return game.world.ecs.names.iter()
+        .find(|(_, n)| n.as_str() == name)
+        .map(|(id, _)| id)
+}
+
+pub fn player_pos(game: &Game) -> Point {
+    return *game.world.ecs.positions.get(PLAYER).unwrap()
+}
+pub fn player_health(game: &Game) -> i32 {
+    return game.world.ecs.healths.get(PLAYER).unwrap().current
+}
+pub fn owned_by_player(game: &Game, id: EntityId) -> bool {
+    return game.world.ecs.inventories.get(id).map_or(false, |inv| inv.owner_id == PLAYER)
+}
+
+//Checks whether any log message reads the given text, ignoring word wrapping padding
+pub fn logged(game: &Game, text: &str) -> bool {
+    return game.logs.iter().any(|msg| {
+        let line = msg.parts.concat();
+        line.split_whitespace().collect::<Vec<&str>>().join(" ").contains(text)
+    })
+}
+
+//Flood fills the walkable tiles of a map from a point, ignoring anything standing on them.
+//Doors count as walkable, open or not.
+pub fn reachable_from(map: &Map, start: Point) -> Vec<bool> {
+    let mut reached = vec![false; map.tiles.len()];
+    let mut open = vec![start];
+    reached[map.index(start.x, start.y)] = true;
+
+    while let Some(p) = open.pop() {
+        for n in p.get_neighbors() {
+            if let Some(idx) = map.try_index(n.x, n.y) {
+                if !reached[idx] && (!map.tiles[idx].does_collide() || map.tiles[idx].is_door()) {
+                    reached[idx] = true;
+                    open.push(n);
+                }
+            }
+        }
+    }
+    return reached
+}
+
+//Hides a trap on the current floor
+pub fn place_trap(game: &mut Game, kind: TrapKind, pos: Point) -> EntityId {
+    let mut trap = make_trap(kind);
+    add_positional_info(&mut trap, pos, game.world.depth);
+    return game.world.ecs.spawn(trap)
+}
+pub fn trap_revealed(game: &Game, id: EntityId) -> bool {
+    return game.world.ecs.traps.get(id).map_or(false, |t| t.revealed)
+}
@@ -0,0 +1,116 @@
+mod common;
+use common::*;
+use darkdelver::prelude::*;
+
+fn tile_at(game: &Game, x: i32, y: i32) -> TileClass {
+    let map = &game.world.active_map;
+    return map.tiles[map.index(x, y)]
+}
+
+#[test]
+fn walking_into_a_closed_door_opens_it() {
+    let mut game = game_from_ascii(&[
+        "#####",
+        "#@+.#",
+        "#####",
+    ]);
+
+    game.advance_turn(Actions::MoveRight);
+    //Opening takes the turn, but doesn't move the player
+    assert!(tile_at(&game, 2, 1) == TileClass::DoorOpen);
+    assert_eq!(player_pos(&game), Point::new(1, 1));
+    assert_eq!(game.world.turns, 1);
+    assert!(logged(&game, "You open the door."));
+
+    game.advance_turn(Actions::MoveRight);
+    assert_eq!(player_pos(&game), Point::new(2, 1));
+}
+
+#[test]
+fn closed_doors_block_sight() {
+    let mut game = game_from_ascii(&[
+        "######",
+        "#@+..#",
+        "######",
+    ]);
+    let map = &game.world.active_map;
+    assert!(!map.visible[map.index(3, 1)]);
+
+    game.advance_turn(Actions::MoveRight);
+    let map = &game.world.active_map;
+    assert!(map.visible[map.index(3, 1)]);
+}
+
+#[test]
+fn closing_a_door_next_to_the_player() {
+    let mut game = game_from_ascii(&[
+        "######",
+        "#.@'.#",
+        "######",
+    ]);
+
+    game.advance_turn(Actions::TryCloseDoor);
+    assert!(tile_at(&game, 3, 1) == TileClass::DoorClosed);
+    assert_eq!(game.world.turns, 1);
+    assert!(logged(&game, "You close the door."));
+
+    //Nothing left to close, so the turn isn't used up
+    game.advance_turn(Actions::TryCloseDoor);
+    assert_eq!(game.world.turns, 1);
+    assert!(logged(&game, "No open door to close!"));
+}
+
+#[test]
+fn doors_with_something_in_them_stay_open() {
+    let mut game = game_from_ascii(&[
+        "######",
+        "#.@e.#",
+        "######",
+    ]);
+    let map = &mut game.world.active_map;
+    let idx = map.index(3, 1);
+    map.tiles[idx] = TileClass::DoorOpen;
+
+    game.advance_turn(Actions::TryCloseDoor);
+    assert!(tile_at(&game, 3, 1) == TileClass::DoorOpen);
+}
+
+#[test]
+fn enemies_open_doors_on_the_way_to_the_player() {
+    let mut game = game_from_ascii(&[
+        "#######",
+        "#@..+e#",
+        "#######",
+    ]);
+    let dummy = find_named(&game, "Test Dummy").unwrap();
+    //The dummy heard something, so it comes looking
+    let ai = game.world.ecs.ais.get_mut(dummy).unwrap();
+    ai.target = Some(PLAYER);
+    ai.tgt_memory = 24;
+    ai.tgt_heatmap.reset_to_single_node(&Point::new(1, 1), 5);
+
+    game.advance_turn(Actions::Wait);
+    assert!(tile_at(&game, 4, 1) == TileClass::DoorOpen);
+    assert_eq!(*game.world.ecs.positions.get(dummy).unwrap(), Point::new(5, 1));
+
+    game.advance_turn(Actions::Wait);
+    assert_eq!(*game.world.ecs.positions.get(dummy).unwrap(), Point::new(4, 1));
+}
+
+#[test]
+fn room_layouts_put_doors_in_corridors() {
+    let mut doors = 0;
+    for mode in [GenerationMode::RandomRooms, GenerationMode::Bsp].iter() {
+        for seed in 0..10 {
+            let gen = MapGenerator::generate(*mode, 60, 60, 1, &mut RandomNumberGenerator::seeded(seed));
+            for (idx, tile) in gen.map.tiles.iter().enumerate() {
+                if !tile.is_door() { continue }
+                doors += 1;
+                let pos = gen.map.point_from_idx(idx);
+                assert!(!gen.rooms.iter().any(|r| r.point_in_rect(pos)), "Door inside a room with seed {}", seed);
+            }
+            assert!(gen.is_winnable());
+        }
+    }
+    assert!(doors > 0);
+}
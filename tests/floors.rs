@@ -0,0 +1,192 @@
+mod common;
+use common::*;
+use darkdelver::prelude::*;
+
+#[test]
+fn descending_stairs_builds_the_next_floor() {
+    let mut game = game_from_ascii(&[
+        "#####",
+        "#@>.#",
+        "#####",
+    ]);
+
+    game.advance_turn(Actions::MoveRight);
+    game.advance_turn(Actions::TryGoDown);
+
+    assert_eq!(game.world.depth, 2);
+    assert_eq!(game.world.ecs.get_floor(PLAYER), 2);
+    assert!(logged(&game, "Descending to level 2..."));
+
+    let pos = player_pos(&game);
+    let map = &game.world.active_map;
+    assert!(!map.tiles[map.index(pos.x, pos.y)].does_collide());
+}
+
+#[test]
+fn stairs_only_work_when_standing_on_them() {
+    let mut game = game_from_ascii(&[
+        "#####",
+        "#@>.#",
+        "#####",
+    ]);
+
+    game.advance_turn(Actions::TryGoDown);
+
+    assert_eq!(game.world.depth, 1);
+    assert_eq!(game.world.turns, 0);
+    assert!(logged(&game, "No stairs to descend!"));
+}
+
+#[test]
+fn items_carried_downstairs_come_along() {
+    let mut game = game_from_ascii(&[
+        "######",
+        "#@!>.#",
+        "######",
+    ]);
+    let potion = find_named(&game, "Potion of Mending").unwrap();
+
+    game.advance_turn(Actions::MoveRight);
+    game.advance_turn(Actions::TryPickUp);
+    game.advance_turn(Actions::MoveRight);
+    game.advance_turn(Actions::TryGoDown);
+
+    assert_eq!(game.world.depth, 2);
+    assert!(game.world.ecs.is_alive(potion));
+    assert!(owned_by_player(&game, potion));
+}
+
+#[test]
+fn a_seed_always_builds_the_same_run() {
+    let a = game_from_seed(1234);
+    let b = game_from_seed(1234);
+
+    assert!(a.world.active_map.tiles == b.world.active_map.tiles);
+    assert_eq!(player_pos(&a), player_pos(&b));
+    assert_eq!(a.world.ecs.entities(), b.world.ecs.entities());
+}
+
+#[test]
+fn a_new_run_starts_with_equipment_on_the_first_floor() {
+    let game = game_from_seed(99);
+
+    assert_eq!(game.world.depth, 1);
+    assert!(find_named(&game, "Iron Longsword").map_or(false, |id| owned_by_player(&game, id)));
+    assert!(logged(&game, "Your adventure begins now."));
+
+    let pos = player_pos(&game);
+    let map = &game.world.active_map;
+    assert!(map.walkable(pos.x, pos.y) || map.objblocked[map.index(pos.x, pos.y)]);
+}
+
+#[test]
+fn floors_below_the_first_start_on_the_way_back_up() {
+    for depth in 1..=4 {
+        let gen = MapGenerator::generate(GenerationMode::for_depth(depth), 60, 60, depth, &mut RandomNumberGenerator::seeded(7));
+        let start = gen.map.tiles[gen.map.index(gen.start_pos.x, gen.start_pos.y)];
+        let up_stairs = gen.map.tiles.iter().filter(|t| **t == TileClass::UpStair).count();
+
+        if depth == 1 {
+            assert!(start == TileClass::Floor);
+            assert_eq!(up_stairs, 0);
+        } else {
+            assert!(start == TileClass::UpStair);
+            assert_eq!(up_stairs, 1);
+        }
+    }
+}
+
+#[test]
+fn there_is_no_way_up_from_the_first_floor() {
+    let mut game = game_from_ascii(&[
+        "#####",
+        "#@>.#",
+        "#####",
+    ]);
+
+    game.advance_turn(Actions::TryGoUp);
+
+    assert_eq!(game.world.depth, 1);
+    assert_eq!(game.world.turns, 0);
+    assert!(logged(&game, "No stairs to ascend!"));
+}
+
+#[test]
+fn climbing_back_up_restores_the_floor_as_it_was_left() {
+    let mut game = game_from_ascii(&[
+        "##########",
+        "#@!>#....#",
+        "#...#...e#",
+        "##########",
+    ]);
+    let potion = find_named(&game, "Potion of Mending").unwrap();
+    let dummy = find_named(&game, "Test Dummy").unwrap();
+    let tiles = game.world.active_map.tiles.to_vec();
+    let revealed = game.world.active_map.revealed.to_vec();
+
+    game.advance_turn(Actions::MoveRight);
+    game.advance_turn(Actions::MoveRight);
+    game.advance_turn(Actions::TryGoDown);
+    assert_eq!(game.world.depth, 2);
+    let pos = player_pos(&game);
+    assert!(game.world.active_map.tiles[game.world.active_map.index(pos.x, pos.y)] == TileClass::UpStair);
+
+    //Nothing left behind moves while the player is away
+    for _ in 0..5 { game.advance_turn(Actions::Wait) }
+    assert_eq!(*game.world.ecs.positions.get(dummy).unwrap(), Point::new(8, 2));
+
+    game.advance_turn(Actions::TryGoUp);
+
+    assert_eq!(game.world.depth, 1);
+    assert!(logged(&game, "Ascending to level 1..."));
+    assert_eq!(player_pos(&game), Point::new(3, 1));
+    assert!(game.world.active_map.tiles == tiles);
+    assert!(game.world.active_map.revealed.iter().zip(revealed.iter()).all(|(now, before)| *now || !*before));
+    assert_eq!(*game.world.ecs.positions.get(potion).unwrap(), Point::new(2, 1));
+    assert_eq!(game.world.ecs.get_floor(potion), 1);
+    assert_eq!(*game.world.ecs.positions.get(dummy).unwrap(), Point::new(8, 2));
+}
+
+#[test]
+fn going_back_down_returns_to_the_same_floor() {
+    let mut game = game_from_ascii(&[
+        "#####",
+        "#@>.#",
+        "#####",
+    ]);
+
+    game.advance_turn(Actions::MoveRight);
+    game.advance_turn(Actions::TryGoDown);
+    let tiles = game.world.active_map.tiles.to_vec();
+    let revealed = game.world.active_map.revealed.to_vec();
+    let entities = game.world.ecs.entities();
+    let arrival = player_pos(&game);
+
+    game.advance_turn(Actions::TryGoUp);
+    assert_eq!(game.world.depth, 1);
+    game.advance_turn(Actions::TryGoDown);
+
+    assert_eq!(game.world.depth, 2);
+    assert_eq!(player_pos(&game), arrival);
+    assert!(game.world.active_map.tiles == tiles);
+    assert!(game.world.active_map.revealed == revealed);
+    assert_eq!(game.world.ecs.entities(), entities);
+    assert!(game.world.stored_maps.contains_key(&1));
+    assert!(!game.world.stored_maps.contains_key(&2));
+}
+
+#[test]
+fn items_on_other_floors_stay_put() {
+    let mut game = game_from_ascii(&[
+        "####",
+        "#@!#",
+        "####",
+    ]);
+    let potion = find_named(&game, "Potion of Mending").unwrap();
+    game.world.ecs.floors.insert(potion, 2);
+
+    game.advance_turn(Actions::MoveRight);
+    game.advance_turn(Actions::TryPickUp);
+
+    assert!(!owned_by_player(&game, potion));
+}
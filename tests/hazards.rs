@@ -0,0 +1,136 @@
+mod common;
+use common::*;
+use darkdelver::prelude::*;
+
+#[test]
+fn wading_into_shallow_water_costs_an_extra_turn() {
+    let mut game = game_from_ascii(&[
+        "######",
+        "#@~..#",
+        "######",
+    ]);
+
+    game.advance_turn(Actions::MoveRight);
+    assert_eq!(player_pos(&game), Point::new(2, 1));
+    assert_eq!(game.world.turns, 2);
+
+    //Walking back out is back to normal speed
+    game.advance_turn(Actions::MoveRight);
+    assert_eq!(game.world.turns, 3);
+}
+
+#[test]
+fn enemies_in_shallow_water_fall_behind() {
+    //The torch lights the player up for the dummy to see from across the room
+    let mut game = game_from_ascii(&[
+        "########",
+        "#@t.~.e#",
+        "########",
+    ]);
+    let dummy = find_named(&game, "Test Dummy").unwrap();
+
+    //Two turns to reach the water, then one sat out in it
+    game.advance_turn(Actions::Wait);
+    game.advance_turn(Actions::Wait);
+    assert_eq!(*game.world.ecs.positions.get(dummy).unwrap(), Point::new(4, 1));
+    game.advance_turn(Actions::Wait);
+    assert_eq!(*game.world.ecs.positions.get(dummy).unwrap(), Point::new(4, 1));
+    game.advance_turn(Actions::Wait);
+    assert_eq!(*game.world.ecs.positions.get(dummy).unwrap(), Point::new(3, 1));
+}
+
+#[test]
+fn lava_burns_every_turn() {
+    let mut game = game_from_ascii(&[
+        "#####",
+        "#@%.#",
+        "#####",
+    ]);
+
+    game.advance_turn(Actions::MoveRight);
+    let once = player_health(&game);
+    assert!(once < 48);
+    assert!(logged(&game, "The lava sears your flesh!"));
+
+    game.advance_turn(Actions::Wait);
+    assert!(player_health(&game) < once);
+}
+
+#[test]
+fn deep_water_washes_items_out_of_the_inventory() {
+    let mut game = game_from_ascii(&[
+        "#####",
+        "#@=.#",
+        "#####",
+    ]);
+    give_items(&mut game.world.ecs, PLAYER, vec![2]);
+    let potion = find_named(&game, "Potion of Mending").unwrap();
+
+    game.advance_turn(Actions::MoveRight);
+    for _ in 0..30 {
+        if !owned_by_player(&game, potion) { break }
+        game.advance_turn(Actions::Wait);
+    }
+
+    assert!(!owned_by_player(&game, potion));
+    assert_eq!(*game.world.ecs.positions.get(potion).unwrap(), Point::new(2, 1));
+    assert!(logged(&game, "slips from your grasp into the water!"));
+}
+
+#[test]
+fn chasms_drop_the_player_to_the_next_floor() {
+    let mut game = game_from_ascii(&[
+        "#####",
+        "#@:.#",
+        "#####",
+    ]);
+
+    game.advance_turn(Actions::MoveRight);
+
+    assert_eq!(game.world.depth, 2);
+    assert_eq!(game.world.ecs.get_floor(PLAYER), 2);
+    assert!(player_health(&game) < 48);
+    assert!(logged(&game, "You fall into the chasm, landing on level 2!"));
+}
+
+#[test]
+fn enemies_go_around_lava_and_chasms() {
+    let mut game = game_from_ascii(&[
+        "#######",
+        "#.....#",
+        "#@.%.e#",
+        "#..:..#",
+        "#######",
+    ]);
+    let dummy = find_named(&game, "Test Dummy").unwrap();
+
+    for _ in 0..4 {
+        game.advance_turn(Actions::Wait);
+        let pos = *game.world.ecs.positions.get(dummy).unwrap();
+        let map = &game.world.active_map;
+        assert!(!map.tiles[map.index(pos.x, pos.y)].is_hazard());
+    }
+    //It still gets there in the end
+    assert!(player_health(&game) < 48);
+}
+
+#[test]
+fn generated_hazards_leave_the_floor_safely_walkable() {
+    let mut hazards = 0;
+    for depth in 1..=6 {
+        for seed in 0..6 {
+            let gen = MapGenerator::generate(GenerationMode::for_depth(depth), 60, 60, depth, &mut RandomNumberGenerator::seeded(seed));
+            assert!(gen.is_winnable(), "Unwinnable floor at depth {} with seed {}", depth, seed);
+            hazards += gen.map.tiles.iter().filter(|t| t.is_hazard()).count();
+
+            for obj in gen.objects.iter() {
+                let pos = obj.pos.unwrap();
+                let tile = gen.map.tiles[gen.map.index(pos.x, pos.y)];
+                assert!(!tile.is_hazard() && tile != TileClass::ShallowWater, "Spawned in a hazard at depth {} with seed {}", depth, seed);
+            }
+            let start = gen.map.tiles[gen.map.index(gen.start_pos.x, gen.start_pos.y)];
+            assert!(start == TileClass::Floor || start == TileClass::UpStair);
+        }
+    }
+    assert!(hazards > 0);
+}
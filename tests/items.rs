@@ -0,0 +1,116 @@
+mod common;
+use common::*;
+use darkdelver::prelude::*;
+
+#[test]
+fn picking_up_an_item_moves_it_into_the_inventory() {
+    let mut game = game_from_ascii(&[
+        "#####",
+        "#@!.#",
+        "#####",
+    ]);
+    let potion = find_named(&game, "Potion of Mending").unwrap();
+
+    game.advance_turn(Actions::MoveRight);
+    game.advance_turn(Actions::TryPickUp);
+
+    assert!(owned_by_player(&game, potion));
+    assert!(!game.world.ecs.positions.contains(potion));
+    assert!(logged(&game, "You pick up Potion of Mending."));
+}
+
+#[test]
+fn picking_up_nothing_does_nothing() {
+    let mut game = game_from_ascii(&[
+        "#####",
+        "#@.!#",
+        "#####",
+    ]);
+    let potion = find_named(&game, "Potion of Mending").unwrap();
+
+    game.advance_turn(Actions::TryPickUp);
+    assert!(!owned_by_player(&game, potion));
+    assert_eq!(*game.world.ecs.positions.get(potion).unwrap(), Point::new(3, 1));
+}
+
+#[test]
+fn dropping_an_item_leaves_it_at_the_players_feet() {
+    let mut game = game_from_ascii(&[
+        "#####",
+        "#!..#",
+        "#@..#",
+        "#####",
+    ]);
+    let potion = find_named(&game, "Potion of Mending").unwrap();
+
+    game.advance_turn(Actions::MoveUp);
+    game.advance_turn(Actions::TryPickUp);
+    game.advance_turn(Actions::MoveRight);
+    let turns = game.world.turns;
+    game.advance_turn(Actions::UseItem(potion, ItemUsage::Drop));
+
+    assert!(!owned_by_player(&game, potion));
+    assert_eq!(*game.world.ecs.positions.get(potion).unwrap(), Point::new(2, 1));
+    assert!(logged(&game, "You have dropped Potion of Mending."));
+    //Dropping things is free
+    assert_eq!(game.world.turns, turns);
+}
+
+#[test]
+fn equipping_and_unequipping_a_weapon_changes_damage() {
+    let mut game = game_from_ascii(&[
+        "###",
+        "#@#",
+        "###",
+    ]);
+    give_items(&mut game.world.ecs, PLAYER, vec![1]);
+    let sword = find_named(&game, "Iron Longsword").unwrap();
+
+    game.advance_turn(Actions::UseItem(sword, ItemUsage::Equip));
+    assert!(game.world.ecs.item_stats.get(sword).unwrap().equipped);
+    let dmg = game.world.ecs.damages.get(PLAYER).unwrap();
+    assert_eq!((dmg.dice, dmg.val), (2, 3));
+    assert!(logged(&game, "You equip the Iron Longsword."));
+
+    game.advance_turn(Actions::UseItem(sword, ItemUsage::Equip));
+    assert!(!game.world.ecs.item_stats.get(sword).unwrap().equipped);
+    let dmg = game.world.ecs.damages.get(PLAYER).unwrap();
+    assert_eq!((dmg.dice, dmg.val), Damage::get_default_damage());
+    assert!(logged(&game, "You unequip the Iron Longsword."));
+}
+
+#[test]
+fn dropping_an_equipped_weapon_unequips_it() {
+    let mut game = game_from_ascii(&[
+        "###",
+        "#@#",
+        "###",
+    ]);
+    give_items(&mut game.world.ecs, PLAYER, vec![1]);
+    let sword = find_named(&game, "Iron Longsword").unwrap();
+
+    game.advance_turn(Actions::UseItem(sword, ItemUsage::Equip));
+    game.advance_turn(Actions::UseItem(sword, ItemUsage::Drop));
+
+    assert!(!game.world.ecs.item_stats.get(sword).unwrap().equipped);
+    let dmg = game.world.ecs.damages.get(PLAYER).unwrap();
+    assert_eq!((dmg.dice, dmg.val), Damage::get_default_damage());
+}
+
+#[test]
+fn drinking_a_potion_heals_and_uses_it_up() {
+    let mut game = game_from_ascii(&[
+        "###",
+        "#@#",
+        "###",
+    ]);
+    give_items(&mut game.world.ecs, PLAYER, vec![2]);
+    let potion = find_named(&game, "Potion of Mending").unwrap();
+    game.world.ecs.healths.get_mut(PLAYER).unwrap().current = 10;
+
+    game.advance_turn(Actions::UseItem(potion, ItemUsage::Drink));
+
+    assert!(player_health(&game) >= 18);
+    assert!(!game.world.ecs.is_alive(potion));
+    assert!(logged(&game, "You drink the Potion of Mending"));
+}
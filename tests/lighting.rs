@@ -0,0 +1,163 @@
+mod common;
+use common::*;
+use darkdelver::prelude::*;
+
+fn light_at(game: &Game, pos: Point) -> f32 {
+    let map = &game.world.active_map;
+    return map.light[map.index(pos.x, pos.y)]
+}
+fn visible(game: &Game, pos: Point) -> bool {
+    let map = &game.world.active_map;
+    return map.visible[map.index(pos.x, pos.y)]
+}
+
+#[test]
+fn torches_light_up_the_area_around_them() {
+    let game = game_from_ascii(&[
+        "######################",
+        "#@..........t........#",
+        "######################",
+    ]);
+
+    let torch = Point::new(12, 1);
+    assert_eq!(light_at(&game, torch), 1.0);
+    assert!(light_at(&game, Point::new(15, 1)) > 0.0 && light_at(&game, Point::new(15, 1)) < light_at(&game, Point::new(13, 1)));
+    assert_eq!(light_at(&game, Point::new(2, 1)), 0.0);
+
+    //The light takes on the torch's colour
+    let map = &game.world.active_map;
+    let color = map.light_color[map.index(13, 1)];
+    assert!(color.r > color.b);
+}
+
+#[test]
+fn walls_keep_the_light_in() {
+    let game = game_from_ascii(&[
+        "#########",
+        "#@..#.t.#",
+        "#########",
+    ]);
+    assert!(light_at(&game, Point::new(5, 1)) > 0.0);
+    assert_eq!(light_at(&game, Point::new(3, 1)), 0.0);
+}
+
+#[test]
+fn opening_a_door_lets_the_glow_through() {
+    let mut game = game_from_ascii(&[
+        "#####",
+        "#@+%#",
+        "#####",
+    ]);
+    assert_eq!(light_at(&game, player_pos(&game)), 0.0);
+
+    game.advance_turn(Actions::MoveRight);
+    let map = &game.world.active_map;
+    assert!(map.tiles[map.index(2, 1)] == TileClass::DoorOpen);
+    assert!(light_at(&game, player_pos(&game)) > 0.0);
+
+    game.advance_turn(Actions::TryCloseDoor);
+    assert_eq!(light_at(&game, player_pos(&game)), 0.0);
+}
+
+#[test]
+fn lit_areas_can_be_seen_from_afar() {
+    let game = game_from_ascii(&[
+        "##########################",
+        "#@.............t.........#",
+        "##########################",
+    ]);
+
+    //The player's own sight only goes so far in the dark, but the torchlight shows up well past it
+    assert!(visible(&game, Point::new(15, 1)));
+    assert!(visible(&game, Point::new(12, 1)));
+    assert!(!visible(&game, Point::new(9, 1)));
+    //Even light can't be made out from too far off
+    assert!(light_at(&game, Point::new(19, 1)) >= LIT_THRESHOLD);
+    assert!(!visible(&game, Point::new(19, 1)));
+}
+
+#[test]
+fn enemies_in_the_dark_go_unseen() {
+    let mut game = game_from_ascii(&[
+        "##########################",
+        "#@...e.............t.....#",
+        "##########################",
+    ]);
+    let dummy = find_named(&game, "Test Dummy").unwrap();
+    game.world.ecs.ais.remove(dummy);
+    game.advance_turn(Actions::Wait);
+
+    //The floor it stands on is in sight, but it can't be made out in the dark
+    assert!(visible(&game, Point::new(5, 1)));
+    assert!(hidden_in_dark(&game.world.ecs, &game.world.active_map, dummy));
+    assert!(!game.world.ecs.memories.get(dummy).unwrap().seen);
+
+    //Close in on it and there it is
+    game.world.ecs.positions.insert(dummy, Point::new(3, 1));
+    game.advance_turn(Actions::Wait);
+    assert!(!hidden_in_dark(&game.world.ecs, &game.world.active_map, dummy));
+    assert!(game.world.ecs.memories.get(dummy).unwrap().seen);
+
+    //Or wait for it to wander into the light, well past the player's own sight
+    game.world.ecs.positions.insert(dummy, Point::new(15, 1));
+    game.advance_turn(Actions::Wait);
+    assert!(!hidden_in_dark(&game.world.ecs, &game.world.active_map, dummy));
+}
+
+#[test]
+fn standing_in_the_light_gives_the_player_away() {
+    let mut game = game_from_ascii(&[
+        "####################",
+        "#@...e.............#",
+        "####################",
+    ]);
+    let dummy = find_named(&game, "Test Dummy").unwrap();
+    game.world.ecs.ais.get_mut(dummy).unwrap().state = AIState::Idle;
+
+    //Sneaking about in the dark goes unnoticed, even well within the dummy's sight
+    game.advance_turn(Actions::Wait);
+    assert!(game.world.ecs.viewsheds.get(dummy).unwrap().visible.contains(&player_pos(&game)));
+    assert!(game.world.ecs.ais.get(dummy).unwrap().state == AIState::Idle);
+
+    //A torch carried along lights the player up for everything to see
+    let mut torch = make_torch();
+    torch.in_inventory = Some(InInventory { owner_id: PLAYER });
+    game.world.ecs.spawn(torch);
+    game.advance_turn(Actions::Wait);
+    assert!(light_at(&game, player_pos(&game)) == 1.0);
+    assert!(game.world.ecs.ais.get(dummy).unwrap().state == AIState::Chasing);
+}
+
+#[test]
+fn lava_and_glowing_enemies_give_off_light() {
+    let game = game_from_ascii(&[
+        "#########",
+        "#@.....%#",
+        "#########",
+    ]);
+    assert!(light_at(&game, Point::new(6, 1)) > 0.0);
+    assert_eq!(light_at(&game, Point::new(2, 1)), 0.0);
+
+    let conn = open_connection();
+    let sporeling = import_enemies_to_objects(&conn, String::from("V_EnemiesFull"), Some(String::from("name = 'Sporeling'"))).unwrap().remove(0);
+    assert!(sporeling.light.map_or(false, |l| l.radius > 0));
+    let beast = import_enemies_to_objects(&conn, String::from("V_EnemiesFull"), Some(String::from("name = 'Bloodsoaked Beast'"))).unwrap().remove(0);
+    assert!(beast.light.is_none());
+}
+
+#[test]
+fn floors_get_torches_put_up_against_the_walls() {
+    for depth in 1..=3 {
+        let mut rng = RandomNumberGenerator::seeded(depth as u64);
+        let mapgen = MapGenerator::for_depth(depth, &mut rng);
+        let torches: Vec<&Object> = mapgen.objects.iter().filter(|o| o.light.is_some()).collect();
+        assert_eq!(torches.len() as i32, LevelProfile::for_depth(depth).light_sources);
+
+        let map = &mapgen.map;
+        for torch in torches.iter() {
+            let pos = torch.pos.unwrap();
+            assert!(!torch.block_tile);
+            assert!(pos.get_neighbors().iter().any(|n| map.try_index(n.x, n.y).map_or(false, |idx| map.tiles[idx] == TileClass::Wall)));
+        }
+    }
+}
@@ -0,0 +1,252 @@
+mod common;
+use common::*;
+use darkdelver::prelude::*;
+
+const WALK: CarverSettings = CarverSettings { floor_percent: 40, lifetime: 200 };
+const MODES: [GenerationMode; 5] = [
+    GenerationMode::RandomRooms, GenerationMode::Bsp, GenerationMode::Caves,
+    GenerationMode::DrunkardsWalk(WALK), GenerationMode::Dla(WALK)
+];
+
+fn generate(mode: GenerationMode, seed: u64) -> MapGenerator {
+    return MapGenerator::generate(mode, 60, 60, 1, &mut RandomNumberGenerator::seeded(seed))
+}
+
+#[test]
+fn every_mode_connects_the_start_to_the_stairs() {
+    for mode in MODES.iter() {
+        for seed in 0..10 {
+            let gen = generate(*mode, seed);
+            let reached = reachable_from(&gen.map, gen.start_pos);
+
+            let stairs = gen.map.tiles.iter().position(|t| *t == TileClass::DownStair).unwrap();
+            assert!(reached[stairs], "Stairs unreachable with seed {}", seed);
+            assert!(gen.is_winnable());
+            for (idx, tile) in gen.map.tiles.iter().enumerate() {
+                assert!(tile.does_collide() || reached[idx], "Stray floor with seed {}", seed);
+            }
+            for region in gen.regions.iter() {
+                let c = region.center;
+                assert!(reached[gen.map.index(c.x, c.y)], "Region unreachable with seed {}", seed);
+            }
+        }
+    }
+}
+
+#[test]
+fn cramped_maps_still_give_a_winnable_floor() {
+    //Too small for twenty rooms, or for most caves, so these lean on the attempt budget and the fallback
+    for mode in MODES.iter() {
+        for seed in 0..10 {
+            let gen = MapGenerator::generate(*mode, 14, 12, 1, &mut RandomNumberGenerator::seeded(seed));
+            assert!(gen.is_winnable(), "Unwinnable cramped map with seed {}", seed);
+            assert!(gen.regions.len() >= 2);
+        }
+    }
+}
+
+#[test]
+fn every_mode_spawns_objects_on_floor() {
+    for mode in MODES.iter() {
+        for seed in 0..10 {
+            let gen = generate(*mode, seed);
+            assert!(!gen.objects.is_empty());
+            for obj in gen.objects.iter() {
+                let pos = obj.pos.unwrap();
+                assert!(!gen.map.tiles[gen.map.index(pos.x, pos.y)].does_collide());
+                assert_eq!(obj.floor, 1);
+            }
+        }
+    }
+}
+
+#[test]
+fn bsp_rooms_do_not_overlap_and_keep_a_wall_border() {
+    for seed in 0..20 {
+        let gen = generate(GenerationMode::Bsp, seed);
+        assert!(gen.rooms.len() >= 4, "Only {} rooms with seed {}", gen.rooms.len(), seed);
+
+        for (i, a) in gen.rooms.iter().enumerate() {
+            assert!(a.x1 > 0 && a.y1 > 0 && a.x2 < gen.map.width && a.y2 < gen.map.height);
+            for b in gen.rooms.iter().skip(i + 1) {
+                assert!(!a.intersect(b), "Rooms overlap with seed {}", seed);
+            }
+        }
+    }
+}
+
+#[test]
+fn caves_have_no_disconnected_pockets() {
+    for seed in 0..10 {
+        let gen = generate(GenerationMode::Caves, seed);
+        let reached = reachable_from(&gen.map, gen.start_pos);
+
+        for (idx, tile) in gen.map.tiles.iter().enumerate() {
+            assert!(tile.does_collide() || reached[idx], "Cut off floor with seed {}", seed);
+        }
+        assert!(gen.rooms.is_empty());
+        assert!(gen.regions.len() > 1);
+        for region in gen.regions.iter() {
+            assert!(region.points.iter().all(|p| !gen.map.tiles[gen.map.index(p.x, p.y)].does_collide()));
+        }
+    }
+}
+
+#[test]
+fn carvers_hit_their_floor_target() {
+    for mode in [GenerationMode::DrunkardsWalk(WALK), GenerationMode::Dla(WALK)].iter() {
+        for seed in 0..5 {
+            let gen = generate(*mode, seed);
+            let floor = gen.map.tiles.iter().filter(|t| !t.does_collide()).count();
+            assert!(floor >= 60 * 60 * 40 / 100, "Only {} floor tiles with seed {}", floor, seed);
+        }
+    }
+}
+
+#[test]
+fn carvers_put_the_stairs_at_the_farthest_tile() {
+    for mode in [GenerationMode::DrunkardsWalk(WALK), GenerationMode::Dla(WALK)].iter() {
+        for seed in 0..5 {
+            let gen = generate(*mode, seed);
+            let start = vec![gen.map.index(gen.start_pos.x, gen.start_pos.y)];
+            let dijkstra = DijkstraMap::new(60, 60, &start, &gen.map, 3600.0);
+
+            let stairs = gen.map.tiles.iter().position(|t| *t == TileClass::DownStair).unwrap();
+            let farthest = dijkstra.map.iter().filter(|d| **d < f32::MAX).fold(0.0, |a: f32, d| a.max(*d));
+            assert_eq!(dijkstra.map[stairs], farthest, "Stairs aren't the farthest tile with seed {}", seed);
+        }
+    }
+}
+
+#[test]
+fn generation_is_repeatable_for_a_seed() {
+    for mode in MODES.iter() {
+        let a = generate(*mode, 77);
+        let b = generate(*mode, 77);
+        assert!(a.map.tiles == b.map.tiles);
+        assert!(a.regions == b.regions);
+        assert_eq!(a.start_pos, b.start_pos);
+    }
+}
+
+#[test]
+fn deeper_floors_switch_layouts() {
+    assert!(GenerationMode::for_depth(1) == GenerationMode::RandomRooms);
+    assert!(GenerationMode::for_depth(2) == GenerationMode::Bsp);
+    assert!(GenerationMode::for_depth(3) == GenerationMode::Caves);
+    match GenerationMode::for_depth(4) { GenerationMode::DrunkardsWalk(_) => {}, _ => panic!("Depth 4 should be a drunkard's walk") }
+    match GenerationMode::for_depth(5) { GenerationMode::Dla(_) => {}, _ => panic!("Depth 5 should be DLA") }
+    assert!(GenerationMode::for_depth(6) == GenerationMode::RandomRooms);
+}
+
+#[test]
+fn vault_templates_load_from_the_database() {
+    let conn = open_connection();
+    let vaults = import_vault_templates(&conn, 99).expect("No vaults found in the database.");
+    assert!(!vaults.is_empty());
+
+    for vault in vaults.iter() {
+        assert!(vault.width() > 0 && vault.height() > 0, "{} has no layout", vault.name);
+        for (glyph, spawn) in vault.spawns.iter() {
+            let found = match spawn {
+                VaultSpawn::Enemy(id) => import_enemies_to_objects(&conn, String::from("V_EnemiesFull"), Some(format!("id = {}", id))),
+                VaultSpawn::Item(id) => import_items_to_objects(&conn, String::from("V_ItemsFull"), Some(format!("id = {}", id)))
+            };
+            assert!(found.map_or(false, |v| !v.is_empty()), "{} has a broken spawn for '{}'", vault.name, glyph);
+        }
+    }
+    //Depth limits are respected
+    assert!(import_vault_templates(&conn, 1).unwrap_or(vec![]).iter().all(|v| v.min_depth <= 1 && v.max_depth >= 1));
+}
+
+#[test]
+fn vaults_get_stamped_without_cutting_off_the_floor() {
+    let mut stamped = 0;
+    for mode in MODES.iter() {
+        for seed in 0..10 {
+            let gen = MapGenerator::generate(*mode, 60, 60, 4, &mut RandomNumberGenerator::seeded(seed));
+            stamped += gen.vaults.len();
+
+            let reached = reachable_from(&gen.map, gen.start_pos);
+            for (idx, tile) in gen.map.tiles.iter().enumerate() {
+                assert!(tile.does_collide() || reached[idx], "Unreachable floor with seed {}", seed);
+            }
+            for vault in gen.vaults.iter() {
+                assert!(!vault.point_in_rect(gen.start_pos));
+                assert!(vault.x1 > 0 && vault.y1 > 0 && vault.x2 < gen.map.width && vault.y2 < gen.map.height);
+            }
+        }
+    }
+    assert!(stamped > 0, "No vaults were ever placed");
+}
+
+#[test]
+fn room_layouts_put_windows_between_rooms() {
+    let mut windows = 0;
+    for mode in [GenerationMode::RandomRooms, GenerationMode::Bsp].iter() {
+        for seed in 0..10 {
+            let gen = generate(*mode, seed);
+            for (idx, tile) in gen.map.tiles.iter().enumerate() {
+                if *tile != TileClass::Glass { continue }
+                windows += 1;
+                let pos = gen.map.point_from_idx(idx);
+                assert!(!gen.rooms.iter().any(|r| r.point_in_rect(pos)), "Window inside a room with seed {}", seed);
+            }
+        }
+    }
+    assert!(windows > 0);
+}
+
+#[test]
+fn level_profiles_load_from_the_database() {
+    let conn = open_connection();
+    let mut last_area = 0;
+    for depth in 1..=12 {
+        let profile = import_level_profile(&conn, MAIN_DUNGEON, depth).expect("No level profile found in the database.");
+        assert!(profile.width >= 20 && profile.height >= 20, "Depth {} is too small", depth);
+        assert!(profile.enemy_density > 0 && profile.item_budget > 0);
+        assert!(get_spawn_table_info(&conn, profile.spawn_table).map_or(false, |t| !t.is_empty()), "Depth {} has no spawn table", depth);
+
+        //Floors never get smaller on the way down
+        assert!(profile.width * profile.height >= last_area, "Depth {} shrank", depth);
+        last_area = profile.width * profile.height;
+    }
+    assert!(import_level_profile(&conn, MAIN_DUNGEON, 1) != import_level_profile(&conn, MAIN_DUNGEON, 12));
+}
+
+#[test]
+fn floors_are_built_to_their_profile() {
+    for depth in [1, 5, 10].iter() {
+        let profile = LevelProfile::for_depth(*depth);
+        let gen = MapGenerator::for_depth(*depth, &mut RandomNumberGenerator::seeded(3));
+
+        assert_eq!((gen.map.width, gen.map.height), (profile.width, profile.height));
+        assert!(gen.is_winnable());
+    }
+    let game = game_from_seed(5);
+    let profile = LevelProfile::for_depth(1);
+    assert_eq!((game.world.active_map.width, game.world.active_map.height), (profile.width, profile.height));
+}
+
+#[test]
+fn profiles_decide_how_much_gets_spawned() {
+    let sparse = LevelProfile { enemy_density: 0, item_budget: 0, ..LevelProfile::fallback(1).with_layout(GenerationMode::RandomRooms, 60, 60) };
+    let packed = LevelProfile { enemy_density: 200, item_budget: 8, ..sparse };
+    let count = |gen: &MapGenerator| -> (usize, usize) {
+        let enemies = gen.objects.iter().filter(|o| o.ai.is_some()).count();
+        let items = gen.objects.iter().filter(|o| o.item_stats.is_some()).count();
+        (enemies, items)
+    };
+
+    for seed in 0..4 {
+        //The layout is laid down before anything is spawned, so both get the same vaults
+        let a = MapGenerator::from_profile(&sparse, 1, &mut RandomNumberGenerator::seeded(seed));
+        let b = MapGenerator::from_profile(&packed, 1, &mut RandomNumberGenerator::seeded(seed));
+        assert!(a.map.tiles == b.map.tiles);
+
+        let (sparse_enemies, sparse_items) = count(&a);
+        let (packed_enemies, packed_items) = count(&b);
+        assert_eq!(packed_enemies - sparse_enemies, packed.enemy_count(b.regions.len() - 1) as usize);
+        assert_eq!(packed_items - sparse_items, 8);
+    }
+}
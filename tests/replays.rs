@@ -0,0 +1,28 @@
+mod common;
+use common::*;
+use darkdelver::prelude::*;
+
+const FIRST_HALF: [Actions; 5] = [Actions::MoveRight, Actions::MoveDown, Actions::Wait, Actions::Search, Actions::MoveLeft];
+const SECOND_HALF: [Actions; 4] = [Actions::MoveUp, Actions::Wait, Actions::MoveRight, Actions::MoveDownRight];
+
+#[test]
+fn a_reloaded_run_keeps_its_whole_action_log() {
+    let mut game = game_from_seed(21);
+    for action in FIRST_HALF.iter() { game.advance_turn(*action) }
+
+    //A round trip through the save format, as a save and reload would do
+    let raw = serde_cbor::to_vec(&game.world).unwrap();
+    let mut reloaded = Game::from_world(serde_cbor::from_slice(&raw).unwrap());
+    reloaded.exec_all_systems();
+    for action in SECOND_HALF.iter() { reloaded.advance_turn(*action) }
+
+    let log = reloaded.world.action_log.clone().expect("A new run keeps its action log");
+    assert_eq!(log.len(), FIRST_HALF.len() + SECOND_HALF.len());
+
+    //Playing the whole log back from the seed ends up just where the reloaded run did
+    let mut replayed = game_from_seed(21);
+    for action in log.into_iter() { replayed.advance_turn(action) }
+    assert_eq!(player_pos(&replayed), player_pos(&reloaded));
+    assert_eq!(replayed.world.turns, reloaded.world.turns);
+    assert_eq!(player_health(&replayed), player_health(&reloaded));
+}
@@ -0,0 +1,95 @@
+mod common;
+use common::*;
+use darkdelver::prelude::*;
+
+#[test]
+fn secret_doors_act_like_walls() {
+    let mut game = game_from_ascii(&[
+        "#######",
+        "#@S...#",
+        "#######",
+    ]);
+
+    game.advance_turn(Actions::MoveRight);
+
+    assert_eq!(player_pos(&game), Point::new(1, 1));
+    let map = &game.world.active_map;
+    assert!(!map.visible[map.index(3, 1)]);
+    assert!(TileClass::SecretDoor.looks_like() == TileClass::Wall);
+}
+
+#[test]
+fn searching_turns_up_secret_doors_in_view() {
+    let mut game = game_from_ascii(&[
+        "#######",
+        "#@.S..#",
+        "#######",
+    ]);
+
+    for _ in 0..30 {
+        if game.world.active_map.tiles[game.world.active_map.index(3, 1)] != TileClass::SecretDoor { break }
+        game.advance_turn(Actions::Search);
+    }
+
+    let map = &game.world.active_map;
+    assert!(map.tiles[map.index(3, 1)] == TileClass::DoorClosed);
+    assert!(map.revealed[map.index(3, 1)]);
+    assert!(logged(&game, "You find a secret door!"));
+    assert!(game.world.turns > 0);
+
+    //Once found it works like any other door
+    game.advance_turn(Actions::MoveRight);
+    game.advance_turn(Actions::MoveRight);
+    game.advance_turn(Actions::MoveRight);
+    assert_eq!(player_pos(&game), Point::new(3, 1));
+}
+
+#[test]
+fn secrets_out_of_sight_stay_hidden() {
+    let mut game = game_from_ascii(&[
+        "#########",
+        "#@..#...#",
+        "#...#.S.#",
+        "#...#...#",
+        "#########",
+    ]);
+
+    for _ in 0..30 { game.advance_turn(Actions::Search) }
+
+    let map = &game.world.active_map;
+    assert!(map.tiles[map.index(6, 2)] == TileClass::SecretDoor);
+    assert!(logged(&game, "You search the area, but find nothing."));
+}
+
+#[test]
+fn found_secret_doors_open_up_pathing() {
+    let mut game = game_from_ascii(&[
+        "#####",
+        "#.S.#",
+        "#@..#",
+        "#####",
+    ]);
+    let map = &mut game.world.active_map;
+    let (left, door) = (map.index(1, 1), map.index(2, 1));
+    assert!(!map.get_available_exits(left).iter().any(|(idx, _)| *idx == door));
+
+    assert!(map.reveal_secret(Point::new(2, 1)));
+    assert!(map.get_available_exits(left).iter().any(|(idx, _)| *idx == door));
+    assert!(!map.reveal_secret(Point::new(2, 1)));
+}
+
+#[test]
+fn generated_secret_doors_never_cut_anything_off() {
+    let mut secrets = 0;
+    for seed in 0..12 {
+        let gen = MapGenerator::generate(GenerationMode::RandomRooms, 60, 60, 1, &mut RandomNumberGenerator::seeded(seed));
+        secrets += gen.map.tiles.iter().filter(|t| **t == TileClass::SecretDoor).count();
+
+        let reached = reachable_from(&gen.map, gen.start_pos);
+        for (idx, tile) in gen.map.tiles.iter().enumerate() {
+            assert!(tile.does_collide() || reached[idx], "Secret door cut off floor with seed {}", seed);
+        }
+        assert!(gen.is_winnable());
+    }
+    assert!(secrets > 0);
+}
@@ -0,0 +1,160 @@
+mod common;
+use common::*;
+use darkdelver::prelude::*;
+
+#[test]
+fn hidden_traps_are_not_remembered() {
+    let mut game = game_from_ascii(&[
+        "#####",
+        "#@..#",
+        "#####",
+    ]);
+    let trap = place_trap(&mut game, TrapKind::Dart, Point::new(3, 1));
+    game.world.ecs.viewsheds.get_mut(PLAYER).unwrap().refresh = true;
+    game.advance_turn(Actions::Wait);
+
+    assert!(game.world.ecs.is_hidden(trap));
+    assert!(!game.world.ecs.memories.get(trap).unwrap().seen);
+}
+
+#[test]
+fn dart_traps_wound_and_reveal_themselves() {
+    let mut game = game_from_ascii(&[
+        "#####",
+        "#@..#",
+        "#####",
+    ]);
+    let trap = place_trap(&mut game, TrapKind::Dart, Point::new(2, 1));
+
+    game.advance_turn(Actions::MoveRight);
+
+    assert!(player_health(&game) < 48);
+    assert!(trap_revealed(&game, trap));
+    assert!(logged(&game, "You set off Dart Trap!"));
+    assert!(game.world.ecs.memories.get(trap).unwrap().seen);
+}
+
+#[test]
+fn teleport_traps_move_the_player_somewhere_else() {
+    let mut game = game_from_ascii(&[
+        "##########",
+        "#@.......#",
+        "#........#",
+        "##########",
+    ]);
+    place_trap(&mut game, TrapKind::Teleport, Point::new(2, 1));
+
+    game.advance_turn(Actions::MoveRight);
+
+    let pos = player_pos(&game);
+    assert!(pos != Point::new(2, 1));
+    assert!(game.world.active_map.walkable(pos.x, pos.y) || game.world.active_map.objblocked[game.world.active_map.index(pos.x, pos.y)]);
+}
+
+#[test]
+fn alarm_traps_bring_enemies_running() {
+    let mut game = game_from_ascii(&[
+        "##########",
+        "#@.#....e#",
+        "#..#.....#",
+        "#........#",
+        "##########",
+    ]);
+    let dummy = find_named(&game, "Test Dummy").unwrap();
+    place_trap(&mut game, TrapKind::Alarm, Point::new(2, 1));
+
+    game.advance_turn(Actions::MoveRight);
+
+    let ai = game.world.ecs.ais.get(dummy).unwrap();
+    assert!(ai.state == AIState::Hunting);
+    assert!(*game.world.ecs.positions.get(dummy).unwrap() != Point::new(8, 1));
+}
+
+#[test]
+fn trapdoors_drop_the_player_a_floor() {
+    let mut game = game_from_ascii(&[
+        "#####",
+        "#@..#",
+        "#####",
+    ]);
+    place_trap(&mut game, TrapKind::Trapdoor, Point::new(2, 1));
+
+    game.advance_turn(Actions::MoveRight);
+
+    assert_eq!(game.world.depth, 2);
+    assert!(logged(&game, "You fall through the trapdoor, landing on level 2!"));
+}
+
+#[test]
+fn enemies_set_off_traps_too() {
+    //The torch lights the player up for the dummy to come after
+    let mut game = game_from_ascii(&[
+        "#######",
+        "#@t..e#",
+        "#######",
+    ]);
+    let dummy = find_named(&game, "Test Dummy").unwrap();
+    place_trap(&mut game, TrapKind::Dart, Point::new(4, 1));
+    game.world.ecs.healths.get_mut(dummy).unwrap().current = 30;
+
+    game.advance_turn(Actions::Wait);
+
+    assert!(game.world.ecs.healths.get(dummy).unwrap().current < 30);
+}
+
+#[test]
+fn searching_finds_nearby_traps() {
+    let mut game = game_from_ascii(&[
+        "#####",
+        "#@..#",
+        "#####",
+    ]);
+    let trap = place_trap(&mut game, TrapKind::Dart, Point::new(3, 1));
+
+    for _ in 0..20 {
+        if trap_revealed(&game, trap) { break }
+        game.advance_turn(Actions::Search);
+    }
+
+    assert!(trap_revealed(&game, trap));
+    assert!(logged(&game, "You find a Dart Trap!"));
+}
+
+#[test]
+fn found_traps_can_be_disarmed() {
+    let mut game = game_from_ascii(&[
+        "#####",
+        "#@..#",
+        "#####",
+    ]);
+    let trap = place_trap(&mut game, TrapKind::Dart, Point::new(2, 1));
+
+    //Nothing known about yet, so there's nothing to disarm and no turn taken
+    game.advance_turn(Actions::TryDisarm);
+    assert_eq!(game.world.turns, 0);
+
+    game.world.ecs.traps.get_mut(trap).unwrap().revealed = true;
+    for _ in 0..20 {
+        if !game.world.ecs.is_alive(trap) { break }
+        game.advance_turn(Actions::TryDisarm);
+    }
+
+    assert!(!game.world.ecs.is_alive(trap));
+    assert!(logged(&game, "You disarm the Dart Trap."));
+    game.advance_turn(Actions::MoveRight);
+    assert_eq!(player_health(&game), 48);
+}
+
+#[test]
+fn floors_get_hidden_traps() {
+    let mut traps = 0;
+    for seed in 0..5 {
+        let gen = MapGenerator::generate(GenerationMode::for_depth(3), 60, 60, 3, &mut RandomNumberGenerator::seeded(seed));
+        for obj in gen.objects.iter().filter(|o| o.trap.is_some()) {
+            traps += 1;
+            assert!(!obj.trap.unwrap().revealed);
+            assert!(obj.pos.unwrap() != gen.start_pos);
+        }
+    }
+    assert!(traps > 0);
+}
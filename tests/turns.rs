@@ -0,0 +1,140 @@
+mod common;
+use common::*;
+use darkdelver::prelude::*;
+
+#[test]
+fn player_moves_onto_open_floor() {
+    let mut game = game_from_ascii(&[
+        "#####",
+        "#...#",
+        "#.@.#",
+        "#...#",
+        "#####",
+    ]);
+
+    game.advance_turn(Actions::MoveRight);
+    assert_eq!(player_pos(&game), Point::new(3, 2));
+    game.advance_turn(Actions::MoveUpLeft);
+    assert_eq!(player_pos(&game), Point::new(2, 1));
+    assert_eq!(game.world.turns, 2);
+}
+
+#[test]
+fn walls_block_movement_without_passing_the_turn() {
+    let mut game = game_from_ascii(&[
+        "###",
+        "#@#",
+        "###",
+    ]);
+
+    game.advance_turn(Actions::MoveLeft);
+    game.advance_turn(Actions::MoveDownRight);
+    assert_eq!(player_pos(&game), Point::new(1, 1));
+    assert_eq!(game.world.turns, 0);
+}
+
+#[test]
+fn glass_can_be_seen_through_but_not_walked_through() {
+    let mut game = game_from_ascii(&[
+        "#######",
+        "#@.\"..#",
+        "#######",
+    ]);
+
+    game.advance_turn(Actions::MoveRight);
+    game.advance_turn(Actions::MoveRight);
+    assert_eq!(player_pos(&game), Point::new(2, 1));
+    let map = &game.world.active_map;
+    assert!(map.visible[map.index(5, 1)]);
+}
+
+#[test]
+fn waiting_passes_the_turn() {
+    let mut game = game_from_ascii(&[
+        "###",
+        "#@#",
+        "###",
+    ]);
+
+    game.advance_turn(Actions::Wait);
+    assert_eq!(game.world.turns, 1);
+    assert!(game.turn_state == TurnState::Player);
+}
+
+#[test]
+fn attacking_wounds_an_enemy_and_it_hits_back() {
+    let mut game = game_from_ascii(&[
+        "#####",
+        "#@e.#",
+        "#####",
+    ]);
+    let dummy = find_named(&game, "Test Dummy").unwrap();
+    game.world.ecs.damages.insert(PLAYER, Damage::new((1, 1)));
+
+    game.advance_turn(Actions::MoveRight);
+
+    //Bumping into the enemy attacks it instead of moving
+    assert_eq!(player_pos(&game), Point::new(1, 1));
+    assert_eq!(game.world.ecs.healths.get(dummy).unwrap().current, 2);
+    assert_eq!(player_health(&game), 47);
+    assert!(logged(&game, "Test Dummy takes 1 damage."));
+    assert!(logged(&game, "You take 1 damage."));
+}
+
+#[test]
+fn killing_an_enemy_leaves_a_corpse() {
+    let mut game = game_from_ascii(&[
+        "#####",
+        "#@e.#",
+        "#####",
+    ]);
+    let dummy = find_named(&game, "Test Dummy").unwrap();
+    game.world.ecs.healths.get_mut(dummy).unwrap().current = 1;
+
+    game.advance_turn(Actions::MoveRight);
+
+    assert!(!game.world.ecs.is_alive(dummy));
+    assert!(logged(&game, "Test Dummy has been slain."));
+    let corpse = find_named(&game, "A Corpse").unwrap();
+    assert_eq!(*game.world.ecs.positions.get(corpse).unwrap(), Point::new(2, 1));
+
+    //The way is clear afterwards
+    game.advance_turn(Actions::MoveRight);
+    assert_eq!(player_pos(&game), Point::new(2, 1));
+}
+
+#[test]
+fn enemies_chase_a_player_they_can_see() {
+    //The torch lights the player up; see the lighting tests for sneaking about in the dark
+    let mut game = game_from_ascii(&[
+        "#######",
+        "#@t..e#",
+        "#######",
+    ]);
+    let dummy = find_named(&game, "Test Dummy").unwrap();
+
+    game.advance_turn(Actions::Wait);
+    assert_eq!(*game.world.ecs.positions.get(dummy).unwrap(), Point::new(4, 1));
+    assert!(game.world.ecs.ais.get(dummy).unwrap().state == AIState::Chasing);
+}
+
+#[test]
+fn player_death_ends_the_game() {
+    let mut game = game_from_ascii(&[
+        "####",
+        "#@e#",
+        "####",
+    ]);
+    let dummy = find_named(&game, "Test Dummy").unwrap();
+    game.world.ecs.damages.insert(dummy, Damage::new((60, 1)));
+
+    game.advance_turn(Actions::Wait);
+
+    assert!(game.turn_state == TurnState::GameOver);
+    assert!(player_health(&game) <= 0);
+    assert!(logged(&game, "You have been slain."));
+    assert!(logged(&game, "You have perished on level 1."));
+    //The player sticks around for everything keyed on it, but is no longer drawn
+    assert!(game.world.ecs.is_alive(PLAYER));
+    assert!(!game.world.ecs.renders.contains(PLAYER));
+}
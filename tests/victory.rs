@@ -0,0 +1,132 @@
+mod common;
+use common::*;
+use darkdelver::prelude::*;
+
+//Starts a run that ends a couple of floors down, and walks to the bottom of it
+fn game_at_final_floor(seed: u64, final_depth: i32) -> Game {
+    let mut game = game_from_seed(seed);
+    game.world.final_depth = final_depth;
+    while game.world.depth < final_depth {
+        take_stairs(&mut game, TileClass::DownStair, Actions::TryGoDown);
+    }
+    return game
+}
+fn goal_on_floor(game: &Game) -> EntityId {
+    let floor = game.world.floor();
+    return game.world.ecs.goals.iter()
+        .map(|(id, _)| id)
+        .find(|id| game.world.ecs.get_floor(*id) == floor)
+        .expect("No goal item on this floor")
+}
+
+#[test]
+fn the_endgame_loads_from_the_database() {
+    let endgame = Endgame::load();
+    assert!(endgame.final_depth > 1);
+
+    let (boss, goal) = get_final_encounter(&endgame);
+    assert!(boss.tag == Some(ActorTag::Enemy) && boss.ai.is_some());
+    assert!(goal.goal && goal.item_stats.is_some());
+
+    let game = game_from_seed(11);
+    assert!(game.world.endgame() == endgame);
+    assert!(game.world.branches.iter().all(|b| b.entry_depth < endgame.final_depth));
+}
+
+#[test]
+fn the_final_floor_holds_the_boss_and_its_prize() {
+    let mut game = game_at_final_floor(4, 3);
+    let boss_name = get_final_encounter(&Endgame::load()).0.name.unwrap();
+
+    let map = &game.world.active_map;
+    assert!(!map.tiles.iter().any(|t| *t == TileClass::DownStair || *t == TileClass::Chasm));
+
+    let goal = goal_on_floor(&game);
+    let lair = *game.world.ecs.positions.get(goal).unwrap();
+    assert!(reachable_from(map, player_pos(&game))[map.index(lair.x, lair.y)]);
+    let boss = find_named(&game, &boss_name).expect("The boss wasn't spawned");
+    assert_eq!(game.world.ecs.get_floor(boss), game.world.floor());
+    assert!(DistanceAlg::Pythagoras.distance2d(lair, *game.world.ecs.positions.get(boss).unwrap()) < 1.5);
+
+    //There's no getting any further down
+    game.advance_turn(Actions::TryGoDown);
+    assert_eq!(game.world.depth, 3);
+    assert!(logged(&game, "No stairs to descend!"));
+}
+
+#[test]
+fn escaping_with_the_goal_item_wins_the_run() {
+    let mut game = game_from_ascii(&[
+        "######",
+        "#<@*.#",
+        "######",
+    ]);
+    game.world.final_depth = 1;
+
+    //Grabbing the prize isn't enough on its own; it still has to be carried back out
+    game.advance_turn(Actions::MoveRight);
+    game.advance_turn(Actions::TryPickUp);
+    assert!(carried_goal_item(&game.world.ecs, PLAYER).is_some());
+    assert!(game.turn_state == TurnState::Player);
+    assert!(logged(&game, "Now to escape back up the stairs with it!"));
+
+    game.advance_turn(Actions::MoveLeft);
+    game.advance_turn(Actions::MoveLeft);
+    game.advance_turn(Actions::TryGoUp);
+    assert!(game.turn_state == TurnState::Victory);
+    assert!(logged(&game, "You escape the depths with the Heart of the Deep!"));
+    assert_eq!(game.world.depth, 1);
+}
+
+#[test]
+fn nothing_gets_another_swing_once_the_run_is_won() {
+    let mut game = game_from_ascii(&[
+        "####",
+        "#@e#",
+        "####",
+    ]);
+    game.world.final_depth = 1;
+    let map = &mut game.world.active_map;
+    let idx = map.index(1, 1);
+    map.tiles[idx] = TileClass::UpStair;
+    let dummy = find_named(&game, "Test Dummy").unwrap();
+    game.world.ecs.damages.insert(dummy, Damage::new((60, 1)));
+
+    let mut goal = get_final_encounter(&Endgame::load()).1;
+    goal.in_inventory = Some(InInventory { owner_id: PLAYER });
+    game.world.ecs.spawn(goal);
+
+    game.advance_turn(Actions::TryGoUp);
+    assert!(game.turn_state == TurnState::Victory);
+    assert!(player_health(&game) > 0);
+}
+
+#[test]
+fn the_final_floor_stairs_lead_back_up_without_the_goal_item() {
+    let mut game = game_at_final_floor(4, 3);
+    assert!(carried_goal_item(&game.world.ecs, PLAYER).is_none());
+
+    take_stairs(&mut game, TileClass::UpStair, Actions::TryGoUp);
+    assert!(game.turn_state == TurnState::Player);
+    assert_eq!(game.world.depth, 2);
+}
+
+#[test]
+fn the_summary_describes_the_run() {
+    let mut game = game_at_final_floor(9, 2);
+    let goal = goal_on_floor(&game);
+    let lair = *game.world.ecs.positions.get(goal).unwrap();
+    game.world.ecs.positions.insert(PLAYER, lair);
+    game.advance_turn(Actions::TryPickUp);
+    take_stairs(&mut game, TileClass::UpStair, Actions::TryGoUp);
+    assert!(game.turn_state == TurnState::Victory);
+
+    let summary = RunSummary::from_world(&game.world);
+    assert_eq!(summary.seed, 9);
+    assert_eq!(summary.turns, game.world.turns);
+    assert_eq!(summary.deepest, 2);
+    assert_eq!(summary.floors_explored, 2);
+    assert!(summary.branches_explored.is_empty());
+    assert_eq!(summary.goal, Some(game.world.ecs.get_name(goal)));
+    assert_eq!(summary.health, (player_health(&game), 48));
+}